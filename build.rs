@@ -0,0 +1,19 @@
+use std::fs;
+
+/// Record the version of `pulldown-cmark` this is built against, from
+/// `Cargo.lock`, for `PULLDOWN_CMARK_VERSION`.
+fn main() {
+	println!("cargo::rerun-if-changed=Cargo.lock");
+
+	let lock = fs::read_to_string("Cargo.lock").unwrap_or_default();
+	let version = lock
+		.split("[[package]]")
+		.find_map(|package| {
+			let mut lines = package.lines().map(str::trim);
+			lines.find(|line| *line == r#"name = "pulldown-cmark""#)?;
+			lines.next()?.strip_prefix(r#"version = ""#)?.strip_suffix('"')
+		})
+		.unwrap_or("unknown");
+
+	println!("cargo::rustc-env=PULLDOWN_CMARK_VERSION={version}");
+}
@@ -1,15 +1,68 @@
 """A configurable wrapper around `pulldown-cmark`."""
 
 from .pulldown_cmark import (
+    PULLDOWN_CMARK_VERSION,
     BadCallbackError,
+    BadFrontmatterError,
+    BadTableError,
+    Cache,
+    CallbackTimeoutError,
+    CannotConfigMathError,
+    CannotRenderMathError,
     Options,
     PulldownCmarkError,
+    Renderer,
+    __version__,
+    extract_links,
+    extract_toc,
+    normalize,
+    parse_events,
+    parse_frontmatter,
     render,
+    render_bytes,
+    render_cached,
+    render_files,
+    render_iter,
+    render_mapping,
+    render_partial,
+    render_stream,
+    render_with_sourcemap,
+    split_frontmatter,
+    stats,
+    to_json,
+    to_plaintext,
+    validate_options,
 )
 
 __all__ = [
+    "PULLDOWN_CMARK_VERSION",
     "BadCallbackError",
+    "BadFrontmatterError",
+    "BadTableError",
+    "Cache",
+    "CallbackTimeoutError",
+    "CannotConfigMathError",
+    "CannotRenderMathError",
     "Options",
     "PulldownCmarkError",
+    "Renderer",
+    "extract_links",
+    "extract_toc",
+    "normalize",
+    "parse_events",
+    "parse_frontmatter",
     "render",
+    "render_bytes",
+    "render_cached",
+    "render_files",
+    "render_iter",
+    "render_mapping",
+    "render_partial",
+    "render_stream",
+    "render_with_sourcemap",
+    "split_frontmatter",
+    "stats",
+    "to_json",
+    "to_plaintext",
+    "validate_options",
 ]
@@ -1,4 +1,14 @@
-from collections.abc import Callable
+from collections.abc import Callable, Hashable, Iterable, Iterator
+from os import PathLike
+from typing import Literal, Protocol, TypedDict, overload
+
+__version__: str
+PULLDOWN_CMARK_VERSION: str
+
+type Output = str | tuple[str, bool] | list[str | tuple[str, bool]]
+type CodeCallback = Callable[[str, str | None], Output]
+type BatchCodeCallback = Callable[[list[tuple[str, str | None]]], list[Output]]
+type BatchMathCallback = Callable[[list[tuple[str, bool]]], list[Output | None]]
 
 class Options:
     tables: bool
@@ -15,8 +25,53 @@ class Options:
     superscript: bool
     subscript: bool
     wikilinks: bool
-    math: Callable[[str, bool], str] | None
-    code: Callable[[str, str | None], str] | None
+    auto_heading_ids: bool
+    base_url: str | None
+    rewrite_md_links: bool
+    sanitize: bool
+    nofollow_external: bool
+    base_host: str | None
+    math_delimiters: list[tuple[str, str, bool]] | None
+    katex: bool
+    heading_offset: int
+    collect_footnotes: bool
+    strip_comments: bool
+    hardbreaks: bool
+    lazy_images: bool
+    smart_quotes_locale: str | None
+    escape_html: bool
+    max_input_bytes: int | None
+    max_nesting_depth: int | None
+    wrapper_tag: str | None
+    wrapper_class: str | None
+    anchor_links: bool
+    anchor_template: str | None
+    dl_class: str | None
+    dt_class: str | None
+    dd_class: str | None
+    admonition_style: Literal["admonition", "github"] | None
+    admonition_titles: dict[str, str] | None
+    max_blocks: int | None
+    autolink: bool
+    strict_tables: bool
+    compact: bool
+    footnote_prefix: str | None
+    allowed_html_tags: list[str] | None
+    disallowed_html: Literal["drop", "escape"] | None
+    figure_images: bool
+    emoji: bool
+    emoji_map: dict[str, str] | None
+    accessible: bool
+    callback_timeout: float | None
+    footnotes_title: str | None
+    slug_style: Literal["ascii", "github"] | None
+    code_block_class: str | None
+    batch_callbacks: bool
+    math: Callable[[str, bool], Output | None] | BatchMathCallback | None
+    code: CodeCallback | dict[str, CodeCallback] | BatchCodeCallback | None
+    broken_link: Callable[[str], tuple[str, str] | None] | None
+    wikilink: Callable[[str], str | tuple[str, str] | None] | None
+    tasklist_marker: Callable[[bool], Output] | None
 
     def __init__(
         self,
@@ -35,11 +90,186 @@ class Options:
         superscript: bool = False,
         subscript: bool = False,
         wikilinks: bool = False,
-        math: Callable[[str, bool], str] | None = None,
-        code: Callable[[str, str | None], str] | None = None,
+        auto_heading_ids: bool = False,
+        base_url: str | None = None,
+        rewrite_md_links: bool = False,
+        sanitize: bool = False,
+        nofollow_external: bool = False,
+        base_host: str | None = None,
+        math_delimiters: list[tuple[str, str, bool]] | None = None,
+        katex: bool = False,
+        heading_offset: int = 0,
+        collect_footnotes: bool = False,
+        strip_comments: bool = False,
+        hardbreaks: bool = False,
+        lazy_images: bool = False,
+        smart_quotes_locale: str | None = None,
+        escape_html: bool = False,
+        max_input_bytes: int | None = None,
+        max_nesting_depth: int | None = None,
+        wrapper_tag: str | None = None,
+        wrapper_class: str | None = None,
+        anchor_links: bool = False,
+        anchor_template: str | None = None,
+        dl_class: str | None = None,
+        dt_class: str | None = None,
+        dd_class: str | None = None,
+        admonition_style: Literal["admonition", "github"] | None = None,
+        admonition_titles: dict[str, str] | None = None,
+        max_blocks: int | None = None,
+        autolink: bool = False,
+        strict_tables: bool = False,
+        compact: bool = False,
+        footnote_prefix: str | None = None,
+        allowed_html_tags: list[str] | None = None,
+        disallowed_html: Literal["drop", "escape"] | None = None,
+        figure_images: bool = False,
+        emoji: bool = False,
+        emoji_map: dict[str, str] | None = None,
+        accessible: bool = False,
+        callback_timeout: float | None = None,
+        footnotes_title: str | None = None,
+        slug_style: Literal["ascii", "github"] | None = None,
+        code_block_class: str | None = None,
+        batch_callbacks: bool = False,
+        math: Callable[[str, bool], Output | None] | BatchMathCallback | None = None,
+        code: CodeCallback | dict[str, CodeCallback] | BatchCodeCallback | None = None,
+        broken_link: Callable[[str], tuple[str, str] | None] | None = None,
+        wikilink: Callable[[str], str | tuple[str, str] | None] | None = None,
+        tasklist_marker: Callable[[bool], Output] | None = None,
     ) -> None: ...
+    @property
+    def extensions(self) -> list[str]: ...
+    @property
+    def callbacks(self) -> list[str]: ...
+    @staticmethod
+    def from_dict(mapping: dict[str, object]) -> Options: ...
+    @staticmethod
+    def gfm_extensions() -> Options: ...
+    @staticmethod
+    def all_extensions() -> Options: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
+class Cache:
+    def __init__(self) -> None: ...
+    def clear(self) -> None: ...
+    def __len__(self) -> int: ...
+
+class Renderer:
+    def __init__(self, options: Options | None = None) -> None: ...
+    def render(self, markdown: str | bytes, context: object = None) -> str: ...
+
+class Stats(TypedDict):
+    words: int
+    characters: int
+    reading_minutes: int
+
+class Reader(Protocol):
+    def read(self, size: int, /) -> str | bytes: ...
+
+class Writer(Protocol):
+    def write(self, s: str, /) -> object: ...
 
 class PulldownCmarkError(Exception): ...
 class BadCallbackError(PulldownCmarkError): ...
+class CannotRenderMathError(BadCallbackError): ...
+class CallbackTimeoutError(BadCallbackError): ...
+class CannotConfigMathError(PulldownCmarkError): ...
+class BadTableError(PulldownCmarkError): ...
+class BadFrontmatterError(PulldownCmarkError): ...
 
-def render(markdown: list[str], options: Options | None = None) -> list[str]: ...
+@overload
+def render(
+    markdown: str | bytes,
+    options: Options | None = None,
+    threads: int | None = None,
+    context: object = None,
+) -> str: ...
+@overload
+def render(
+    markdown: Iterable[str | bytes],
+    options: Options | None = None,
+    threads: int | None = None,
+    context: list[object] | None = None,
+) -> list[str]: ...
+@overload
+def render_bytes(
+    markdown: str | bytes,
+    options: Options | None = None,
+    threads: int | None = None,
+    context: object = None,
+) -> bytes: ...
+@overload
+def render_bytes(
+    markdown: Iterable[str | bytes],
+    options: Options | None = None,
+    threads: int | None = None,
+    context: list[object] | None = None,
+) -> list[bytes]: ...
+def render_cached(
+    markdown: Iterable[str | bytes],
+    options: Options | None,
+    cache: Cache,
+) -> list[str]: ...
+def render_files(
+    paths: Iterable[str | PathLike[str]],
+    options: Options | None = None,
+    context: list[object] | None = None,
+) -> list[str]: ...
+def render_iter(
+    markdown: Iterable[str | bytes],
+    options: Options | None = None,
+) -> Iterator[str]: ...
+def render_mapping[K: Hashable](
+    documents: dict[K, str],
+    options: Options | None = None,
+) -> dict[K, str]: ...
+def render_partial(
+    markdown: Iterable[str | bytes],
+    options: Options | None = None,
+) -> list[str | PulldownCmarkError]: ...
+def render_stream(reader: Reader, writer: Writer, options: Options | None = None) -> None: ...
+def render_with_sourcemap(
+    markdown: str,
+    options: Options | None = None,
+) -> tuple[str, list[tuple[int, int, int]]]: ...
+@overload
+def extract_links(
+    markdown: Iterable[str | bytes],
+    options: Options | None = None,
+    *,
+    with_offsets: Literal[False] = False,
+) -> list[list[str]]: ...
+@overload
+def extract_links(
+    markdown: Iterable[str | bytes],
+    options: Options | None = None,
+    *,
+    with_offsets: Literal[True],
+) -> list[list[tuple[str, int, int]]]: ...
+@overload
+def extract_toc(
+    markdown: str,
+    options: Options | None = None,
+    *,
+    with_offsets: Literal[False] = False,
+) -> list[tuple[int, str, str]]: ...
+@overload
+def extract_toc(
+    markdown: str,
+    options: Options | None = None,
+    *,
+    with_offsets: Literal[True],
+) -> list[tuple[int, str, str, int, int]]: ...
+def normalize(markdown: Iterable[str | bytes], options: Options | None = None) -> list[str]: ...
+def parse_events(
+    markdown: str,
+    options: Options | None = None,
+) -> list[dict[str, object]]: ...
+def split_frontmatter(markdown: str) -> tuple[str | None, str]: ...
+def parse_frontmatter(markdown: str) -> tuple[dict[str, object] | None, str]: ...
+def to_json(markdown: Iterable[str | bytes], options: Options | None = None) -> list[str]: ...
+def to_plaintext(markdown: Iterable[str | bytes], options: Options | None = None) -> list[str]: ...
+def stats(markdown: Iterable[str | bytes], options: Options | None = None) -> list[Stats]: ...
+def validate_options(options: Options) -> None: ...
@@ -0,0 +1,346 @@
+use crate::url::is_unsafe;
+use pulldown_cmark_escape::escape_html;
+use std::mem::take;
+
+/// The attributes kept on allowed tags. Others, such as `style` and event
+/// handlers like `onclick`, are dropped.
+const SAFE_ATTRIBUTES: &[&str] = &[
+	"alt", "cite", "class", "colspan", "datetime", "dir", "height", "href", "lang", "rowspan", "src", "title",
+	"width",
+];
+
+/// The attributes of `SAFE_ATTRIBUTES` which are URLs, kept only if they're safe.
+const URL_ATTRIBUTES: &[&str] = &["cite", "href", "src"];
+
+/// Elements whose content is text up to their end tag, rather than HTML, so it
+/// goes with them when they're dropped.
+const RAW_TEXT: &[&str] = &[
+	"iframe", "noembed", "noframes", "noscript", "script", "style", "textarea", "title", "xmp",
+];
+
+/// A filter of raw HTML to the tags of `allowed_html_tags`, which escapes or
+/// drops the rest per `disallowed_html`. Comments, declarations, and stray `<`
+/// are disallowed too.
+///
+/// A tag may span the chunks of an HTML block, so a tag cut off at the end of
+/// a chunk is held for the next, until `finish` ends the block.
+pub struct Allowlist<'c> {
+	allowed: &'c [String],
+	escape: bool,
+	/// The start of a tag cut off at the end of the last chunk.
+	partial: String,
+	/// The open raw text element, if any, and whether it's allowed.
+	raw: Option<(String, bool)>,
+}
+
+impl<'c> Allowlist<'c> {
+	pub fn new(allowed: Option<&'c [String]>, escape: bool) -> Self {
+		Self {
+			allowed: allowed.unwrap_or_default(),
+			escape,
+			partial: String::new(),
+			raw: None,
+		}
+	}
+
+	/// Filter a chunk of raw HTML, holding any tag it cuts off.
+	pub fn filter(&mut self, html: &str) -> String {
+		let input = take(&mut self.partial) + html;
+		let mut output = String::with_capacity(input.len());
+		let mut rest = input.as_str();
+
+		loop {
+			if let Some((name, allowed)) = &self.raw {
+				let end = find_end_tag(rest, name).unwrap_or(rest.len());
+				let allowed = *allowed;
+				self.reject_unless(allowed, &mut output, &rest[..end]);
+				rest = &rest[end..];
+
+				if rest.is_empty() {
+					break;
+				}
+
+				self.raw = None;
+			}
+
+			let Some(start) = rest.find('<') else {
+				output.push_str(rest);
+				break;
+			};
+
+			output.push_str(&rest[..start]);
+			rest = &rest[start..];
+
+			let length = match token(rest) {
+				Token::Incomplete => {
+					self.partial = rest.to_owned();
+					break;
+				}
+
+				Token::Stray => {
+					output.push_str("&lt;");
+					1
+				}
+
+				Token::Markup(length) => {
+					self.reject_unless(false, &mut output, &rest[..length]);
+					length
+				}
+
+				Token::Tag(tag, length) => {
+					let allowed =
+						self.allowed.iter().any(|name| name.eq_ignore_ascii_case(&tag.name));
+
+					match allowed {
+						true => output.push_str(&tag.render()),
+						false => self.reject_unless(false, &mut output, &rest[..length]),
+					}
+
+					if !tag.end && !tag.self_closing && RAW_TEXT.contains(&tag.name.as_str()) {
+						self.raw = Some((tag.name, allowed));
+					}
+
+					length
+				}
+			};
+
+			rest = &rest[length..];
+		}
+
+		output
+	}
+
+	/// End a run of chunks, rejecting any tag left cut off as malformed.
+	pub fn finish(&mut self) -> String {
+		let mut output = String::new();
+		let partial = take(&mut self.partial);
+		self.reject_unless(false, &mut output, &partial);
+		self.raw = None;
+		output
+	}
+
+	/// Keep `html` if it's allowed, and otherwise escape or drop it.
+	fn reject_unless(&self, allowed: bool, output: &mut String, html: &str) {
+		match (allowed, self.escape) {
+			(true, _) => output.push_str(html),
+			/* Writing to a `String` can't fail. */
+			(false, true) => escape_html(output, html).unwrap(),
+			(false, false) => {}
+		}
+	}
+}
+
+enum Token<'a> {
+	/// A start or end tag, and its length.
+	Tag(ParsedTag<'a>, usize),
+	/// A comment, declaration, processing instruction, or CDATA section, and
+	/// its length.
+	Markup(usize),
+	/// Markup which continues past the end of the input.
+	Incomplete,
+	/// A `<` which starts no markup.
+	Stray,
+}
+
+/// The name and value, if any, of an attribute.
+type Attribute<'a> = (&'a str, Option<&'a str>);
+
+struct ParsedTag<'a> {
+	/// The lowercase name of the tag.
+	name: String,
+	end: bool,
+	self_closing: bool,
+	attrs: Vec<Attribute<'a>>,
+}
+
+impl ParsedTag<'_> {
+	/// Render the tag with only its safe attributes, normalized to be quoted.
+	fn render(&self) -> String {
+		if self.end {
+			return format!("</{}>", self.name);
+		}
+
+		let mut html = format!("<{}", self.name);
+
+		for &(name, value) in &self.attrs {
+			let name = name.to_ascii_lowercase();
+
+			if !SAFE_ATTRIBUTES.contains(&name.as_str()) {
+				continue;
+			}
+
+			match value {
+				Some(value) if URL_ATTRIBUTES.contains(&name.as_str()) && is_unsafe(&decode(value)) => {
+				}
+				/* Entities in the value are kept, as the browser will decode them. */
+				Some(value) => {
+					let value =
+						value.replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;");
+					html.push_str(&format!(" {name}=\"{value}\""));
+				}
+				None => html.push_str(&format!(" {name}")),
+			}
+		}
+
+		html.push_str(if self.self_closing { " />" } else { ">" });
+		html
+	}
+}
+
+/// Read the markup at the start of `html`, which starts with `<`.
+fn token(html: &str) -> Token<'_> {
+	let bytes = html.as_bytes();
+
+	let closer = match bytes.get(1) {
+		None => return Token::Incomplete,
+		Some(b'!') if html.starts_with("<!--") => "-->",
+		Some(b'!') if html.starts_with("<![CDATA[") => "]]>",
+		Some(b'!' | b'?') => ">",
+		Some(b'/') if bytes.len() == 2 => return Token::Incomplete,
+		Some(b'/') if bytes[2].is_ascii_alphabetic() => return tag(html),
+		Some(c) if c.is_ascii_alphabetic() => return tag(html),
+		Some(_) => return Token::Stray,
+	};
+
+	match html[2..].find(closer) {
+		Some(end) => Token::Markup(2 + end + closer.len()),
+		None => Token::Incomplete,
+	}
+}
+
+/// Read the start or end tag at the start of `html`.
+fn tag(html: &str) -> Token<'_> {
+	let bytes = html.as_bytes();
+	let end = bytes[1] == b'/';
+
+	let start = 1 + usize::from(end);
+	let mut i = skip(bytes, start, |c| c.is_ascii_alphanumeric() || c == b'-');
+	let name = html[start..i].to_ascii_lowercase();
+	let mut attrs = Vec::new();
+
+	loop {
+		i = skip(bytes, i, |c| c.is_ascii_whitespace());
+
+		let self_closing = match (bytes.get(i), bytes.get(i + 1)) {
+			(None, _) | (Some(b'/'), None) => return Token::Incomplete,
+			(Some(b'>'), _) => false,
+			(Some(b'/'), Some(b'>')) => true,
+			(Some(b'/'), _) => {
+				i += 1;
+				continue;
+			}
+			_ => {
+				let (attr, length) = match attribute(&html[i..]) {
+					Some(attribute) => attribute,
+					None => return Token::Incomplete,
+				};
+
+				attrs.push(attr);
+				i += length;
+				continue;
+			}
+		};
+
+		let tag = ParsedTag {
+			name,
+			end,
+			self_closing,
+			attrs,
+		};
+
+		return Token::Tag(tag, i + 1 + usize::from(self_closing));
+	}
+}
+
+/// Read the attribute at the start of `html`, and its length, or `None` if its
+/// value continues past the end of the input.
+fn attribute(html: &str) -> Option<(Attribute<'_>, usize)> {
+	let bytes = html.as_bytes();
+
+	/* A name may start with `=`, e.g. in `<b =x>`, but not otherwise contain it. */
+	let name_end = skip(bytes, 1, |c| {
+		!c.is_ascii_whitespace() && !matches!(c, b'/' | b'>' | b'=')
+	});
+	let name = &html[..name_end];
+	let equals = skip(bytes, name_end, |c| c.is_ascii_whitespace());
+
+	if bytes.get(equals) != Some(&b'=') {
+		return Some(((name, None), name_end));
+	}
+
+	let start = skip(bytes, equals + 1, |c| c.is_ascii_whitespace());
+
+	match bytes.get(start)? {
+		&quote @ (b'"' | b'\'') => {
+			let length = html[start + 1..].find(char::from(quote))?;
+			let end = start + 1 + length;
+			Some(((name, Some(&html[start + 1..end])), end + 1))
+		}
+
+		_ => {
+			let end = skip(bytes, start, |c| !c.is_ascii_whitespace() && c != b'>');
+			Some(((name, Some(&html[start..end])), end))
+		}
+	}
+}
+
+/// The index of the first byte of `bytes`, from `i` on, for which `f` is false.
+fn skip(bytes: &[u8], mut i: usize, f: fn(u8) -> bool) -> usize {
+	while bytes.get(i).copied().is_some_and(f) {
+		i += 1;
+	}
+
+	i
+}
+
+/// The offset of the end tag of `name` in `html`, ignoring case.
+fn find_end_tag(html: &str, name: &str) -> Option<usize> {
+	html.to_ascii_lowercase().find(&format!("</{name}"))
+}
+
+/// Decode the character references in an attribute value which can hide a URL
+/// scheme from `is_unsafe`, e.g. `&#106;avascript:` or `javascript&colon;`.
+fn decode(value: &str) -> String {
+	let mut output = String::with_capacity(value.len());
+	let mut rest = value;
+
+	while let Some(start) = rest.find('&') {
+		output.push_str(&rest[..start]);
+		rest = &rest[start + 1..];
+
+		match reference(rest) {
+			Some((c, length)) => {
+				output.push(c);
+				rest = &rest[length..];
+			}
+
+			None => output.push('&'),
+		}
+	}
+
+	output.push_str(rest);
+	output
+}
+
+/// The character of the reference at the start of `text`, after its `&`, and
+/// the length of the rest of the reference.
+fn reference(text: &str) -> Option<(char, usize)> {
+	for (name, c) in [("colon;", ':'), ("tab;", '\t'), ("newline;", '\n')] {
+		if text.get(..name.len())
+			.is_some_and(|prefix| prefix.eq_ignore_ascii_case(name))
+		{
+			return Some((c, name.len()));
+		}
+	}
+
+	let (digits, radix, prefix) = match text.strip_prefix('#')? {
+		hex if hex.starts_with(['x', 'X']) => (&hex[1..], 16, 2),
+		decimal => (decimal, 10, 1),
+	};
+
+	let length = digits.find(|c: char| !c.is_digit(radix)).unwrap_or(digits.len());
+	let c = char::from_u32(u32::from_str_radix(&digits[..length], radix).ok()?)?;
+	let semicolon = usize::from(digits[length..].starts_with(';'));
+
+	Some((c, prefix + length + semicolon))
+}
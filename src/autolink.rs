@@ -0,0 +1,88 @@
+use ::pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
+
+/// Split text into text and links around the bare URLs in it, e.g.
+/// `https://example.com` and `www.example.com`, as GFM's extended autolinks.
+///
+/// A URL starts a word and runs to the next space or `<`, less any trailing
+/// punctuation and any `)` it doesn't open, so `(see https://x.com).` links
+/// `https://x.com`. `www.` URLs link to `http://`.
+pub fn autolink<'p>(text: &str) -> Vec<Event<'p>> {
+	let mut events = Vec::new();
+	let mut rest = text;
+
+	while let Some((start, end)) = find(rest) {
+		let url = &rest[start..end];
+
+		if start > 0 {
+			events.push(Event::Text(rest[..start].to_owned().into()));
+		}
+
+		let dest_url = match url.starts_with("www.") {
+			true => format!("http://{url}"),
+			false => url.to_owned(),
+		};
+
+		events.push(Event::Start(Tag::Link {
+			link_type: LinkType::Autolink,
+			dest_url: dest_url.into(),
+			title: CowStr::Borrowed(""),
+			id: CowStr::Borrowed(""),
+		}));
+
+		events.push(Event::Text(url.to_owned().into()));
+		events.push(Event::End(TagEnd::Link));
+		rest = &rest[end..];
+	}
+
+	if !rest.is_empty() || events.is_empty() {
+		events.push(Event::Text(rest.to_owned().into()));
+	}
+
+	events
+}
+
+/// The byte range of the first URL in `text`.
+fn find(text: &str) -> Option<(usize, usize)> {
+	let mut offset = 0;
+
+	loop {
+		let start = offset
+			+ ["https://", "http://", "www."]
+				.iter()
+				.filter_map(|prefix| text[offset..].find(prefix))
+				.min()?;
+		let previous = text[..start].chars().next_back();
+
+		/* The URL must start a word, rather than e.g. end `xhttps://`. */
+		if previous.is_none_or(|c| c.is_whitespace() || "*_~([".contains(c)) {
+			let length = text[start..]
+				.find(|c: char| c.is_whitespace() || c == '<')
+				.unwrap_or(text.len() - start);
+			let url = trim(&text[start..start + length]);
+			let host = url.split_once("//").map_or(url, |(_, host)| host);
+
+			if host.trim_start_matches("www.")
+				.chars()
+				.next()
+				.is_some_and(char::is_alphanumeric)
+			{
+				return Some((start, start + url.len()));
+			}
+		}
+
+		/* Every prefix starts with an ASCII letter, so this is a boundary. */
+		offset = start + 1;
+	}
+}
+
+/// Trim the trailing punctuation of a URL, and any `)` it doesn't open.
+fn trim(mut url: &str) -> &str {
+	loop {
+		let trimmed = url.trim_end_matches(['?', '!', '.', ',', ':', ';', '*', '_', '~', '\'', '"']);
+
+		url = match trimmed.strip_suffix(')') {
+			Some(rest) if trimmed.matches('(').count() < trimmed.matches(')').count() => rest,
+			_ => return trimmed,
+		};
+	}
+}
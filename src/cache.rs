@@ -0,0 +1,159 @@
+use crate::options::PyOptions;
+use crate::{inputs, render_one};
+use pyo3::{PyTraverseError, PyVisit, prelude::*};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// A cache of rendered HTML for `render_cached`, keyed by a BLAKE3 hash of
+/// each Markdown string and its options, e.g. to skip unchanged files in an
+/// incremental build. It's safe to share between threads.
+///
+/// Options are keyed by their constructor keywords and the identity of their
+/// callbacks, so a callback whose output changes, e.g. with a new theme,
+/// needs a new cache or `clear`. Each entry keeps the callbacks it was
+/// rendered with alive, so a new callback can't take the identity of one
+/// which was garbage-collected.
+#[pyclass(name = "Cache", module = "pulldown_cmark", frozen)]
+#[derive(Default)]
+pub struct Cache {
+	entries: Mutex<HashMap<[u8; 32], Entry>>,
+}
+
+/// The HTML of a Markdown string, and the callbacks it was rendered with,
+/// shared by the entries of a call.
+struct Entry {
+	html: String,
+	callbacks: Arc<Vec<PyObject>>,
+}
+
+impl Entry {
+	/// Whether the entry was rendered with exactly `callbacks`, by identity.
+	fn rendered_with(&self, callbacks: &[PyObject]) -> bool {
+		self.callbacks.len() == callbacks.len()
+			&& self.callbacks
+				.iter()
+				.zip(callbacks)
+				.all(|(callback, other)| callback.is(other))
+	}
+}
+
+#[pymethods]
+impl Cache {
+	#[new]
+	fn new() -> Self {
+		Self::default()
+	}
+
+	/// Remove every entry.
+	fn clear(&self) {
+		self.entries.lock().unwrap().clear();
+	}
+
+	fn __len__(&self) -> usize {
+		self.entries.lock().unwrap().len()
+	}
+
+	/// Visit the callbacks of the entries, once for each call which rendered
+	/// them, as the entries of a call share one reference to each.
+	fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+		/* A render holding the lock only hides references, which keeps the
+		 * callbacks alive until the next collection. */
+		let Ok(entries) = self.entries.try_lock() else {
+			return Ok(());
+		};
+
+		let mut visited = HashSet::new();
+
+		for entry in entries.values() {
+			if visited.insert(Arc::as_ptr(&entry.callbacks)) {
+				for callback in entry.callbacks.iter() {
+					visit.call(callback)?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	fn __clear__(&self) {
+		self.clear();
+	}
+}
+
+/// Render a list of Markdown strings into a list of HTML strings, as `render`
+/// does, taking the HTML of strings rendered before from `cache`, and adding
+/// the rest to it.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     An iterable of Markdown `str` or UTF-8 `bytes` to render.
+/// options
+///     The Markdown extensions to enable.
+/// cache
+///     The `Cache` to render through.
+///
+/// Returns
+/// -------
+/// A list of HTML strings which preserves the indices of `markdown`.
+///
+/// Raises
+/// ------
+/// BadCallbackError
+///    If a user callback fails while Markdown is parsed. Failures aren't
+///    cached.
+/// BadTableError
+///    If `strict_tables` is set and a table row doesn't have one cell for each
+///    column of its header.
+/// ValueError
+///    If an input exceeds `max_input_bytes` or `max_nesting_depth`.
+#[pyfunction]
+#[pyo3(signature = (markdown, options, cache))]
+pub fn render_cached(
+	py: Python,
+	markdown: &Bound<'_, PyAny>,
+	options: Option<&PyOptions>,
+	cache: &Bound<'_, Cache>,
+) -> PyResult<Vec<String>> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let inputs = inputs(markdown)?;
+	let key = options.key(py)?;
+	let callbacks = Arc::new(options.callbacks.set(py));
+	let cache = cache.get();
+
+	py.allow_threads(move || {
+		inputs.par_iter()
+			.enumerate()
+			.map(|(index, buffer)| {
+				let hash = hash(&key, buffer);
+
+				if let Some(entry) = cache.entries.lock().unwrap().get(&hash) {
+					if entry.rendered_with(&callbacks) {
+						return Ok(entry.html.clone());
+					}
+				}
+
+				let html = render_one(buffer, options, None).map_err(|err| err.at(index))?;
+				let entry = Entry {
+					html: html.clone(),
+					callbacks: Arc::clone(&callbacks),
+				};
+
+				cache.entries.lock().unwrap().insert(hash, entry);
+				Ok(html)
+			})
+			.collect()
+	})
+}
+
+/// The BLAKE3 hash of a Markdown string and the `key` of its options.
+fn hash(key: &str, markdown: &str) -> [u8; 32] {
+	let mut hasher = blake3::Hasher::new();
+	/* The length keeps the boundary between the two unambiguous. */
+	hasher.update(&(key.len() as u64).to_le_bytes());
+	hasher.update(key.as_bytes());
+	hasher.update(markdown.as_bytes());
+	*hasher.finalize().as_bytes()
+}
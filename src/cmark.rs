@@ -0,0 +1,50 @@
+use crate::error::Fatal;
+use crate::inputs;
+use crate::options::PyOptions;
+use ::pulldown_cmark::Parser;
+use pulldown_cmark_to_cmark::cmark;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Normalize a list of Markdown strings into canonical CommonMark.
+///
+/// Each string is parsed and serialized again, so equivalent syntax comes out
+/// the same, e.g. setext headings become ATX headings, and `~~~` fences become
+/// backtick fences. The `math` and `code` callbacks are not called.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     An iterable of Markdown `str` or UTF-8 `bytes` to normalize.
+/// options
+///     The Markdown extensions to enable.
+///
+/// Returns
+/// -------
+/// A list of Markdown strings which preserves the indices of `markdown`.
+///
+/// Raises
+/// ------
+/// PulldownCmarkError
+///     If an input can't be written back as Markdown with these extensions.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None))]
+pub fn normalize(py: Python, markdown: &Bound<'_, PyAny>, options: Option<&PyOptions>) -> PyResult<Vec<String>> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let inputs = inputs(markdown)?;
+
+	py.allow_threads(move || {
+		inputs.par_iter()
+			.enumerate()
+			.map(|(index, buffer)| {
+				let mut output = String::with_capacity(buffer.len());
+				cmark(Parser::new_ext(buffer, options.flags), &mut output, options.flags)
+					/* A leading code block is written after a blank line. */
+					.map(|_| String::from(output.trim_start_matches('\n')))
+					.map_err(|err| Fatal::from(err).at(index))
+			})
+			.collect::<Result<Vec<String>, Fatal>>()
+			.map_err(PyErr::from)
+	})
+}
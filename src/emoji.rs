@@ -0,0 +1,50 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Replace the shortcodes in `text`, e.g. `:smile:`, with their emoji, from
+/// `map` or else GitHub's table. Unknown shortcodes are left as they are.
+pub fn emojify<'a>(text: &'a str, map: Option<&HashMap<String, String>>) -> Cow<'a, str> {
+	if !text.contains(':') {
+		return Cow::Borrowed(text);
+	}
+
+	let mut output = String::with_capacity(text.len());
+	let mut rest = text;
+
+	while let Some(start) = rest.find(':') {
+		output.push_str(&rest[..start]);
+		rest = &rest[start + 1..];
+
+		/* The closing colon may open the next shortcode, e.g. in `a:b:smile:`. */
+		let emoji = rest
+			.split_once(':')
+			.filter(|(name, _)| is_name(name))
+			.and_then(|(name, after)| Some((lookup(name, map)?, after)));
+
+		match emoji {
+			Some((emoji, after)) => {
+				output.push_str(emoji);
+				rest = after;
+			}
+
+			None => output.push(':'),
+		}
+	}
+
+	output.push_str(rest);
+	Cow::Owned(output)
+}
+
+/// Whether `name` can be a shortcode, e.g. `+1` or `heavy_check_mark`.
+fn is_name(name: &str) -> bool {
+	!name.is_empty()
+		&& name.bytes()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'+' | b'-'))
+}
+
+fn lookup<'a>(name: &str, map: Option<&'a HashMap<String, String>>) -> Option<&'a str> {
+	match map.and_then(|map| map.get(name)) {
+		Some(emoji) => Some(emoji),
+		None => emojis::get_by_shortcode(name).map(|emoji| emoji.as_str()),
+	}
+}
@@ -1,20 +1,107 @@
-use pyo3::{PyErr, create_exception, exceptions::PyException};
+use pyo3::{
+	PyErr, Python, create_exception,
+	exceptions::{PyException, PyOSError, PyValueError},
+};
+use std::io;
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum Fatal {
 	#[error("a user callback failed")]
 	BadCallback(#[from] PyErr),
+
+	#[error("cannot read `{}`: {source}", path.display())]
+	Read { path: PathBuf, source: io::Error },
+
+	#[error("cannot write Markdown: {0}")]
+	Unrepresentable(#[from] pulldown_cmark_to_cmark::Error),
+
+	#[error("a user callback exceeded `callback_timeout` of {timeout} seconds")]
+	TimedOut { timeout: f64 },
+
+	#[error("cannot render math `{latex}`")]
+	MathFailed { latex: String, source: PyErr },
+
+	#[error("input of {size} bytes exceeds `max_input_bytes` of {limit}")]
+	TooLarge { size: usize, limit: usize },
+
+	#[error("nesting exceeds `max_nesting_depth` of {limit}")]
+	TooDeep { limit: usize },
+
+	#[error("table body row {row} has {cells} cells, but the header has {columns}")]
+	BadTable { row: usize, cells: usize, columns: usize },
+
+	#[error("cannot parse {format} front matter: {message}")]
+	BadFrontmatter { format: &'static str, message: String },
+
+	#[error("{source} while rendering input {index}")]
+	AtInput { index: usize, source: Box<Fatal> },
+
+	#[error("{source} while rendering document {key}")]
+	AtKey { key: String, source: Box<Fatal> },
+}
+
+impl Fatal {
+	/// Attach the index of the input which was being rendered.
+	pub fn at(self, index: usize) -> Self {
+		Fatal::AtInput {
+			index,
+			source: Box::new(self),
+		}
+	}
+
+	/// Attach the `repr` of the mapping key of the document which was being
+	/// rendered.
+	pub fn at_key(self, key: String) -> Self {
+		Fatal::AtKey {
+			key,
+			source: Box::new(self),
+		}
+	}
+
+	/// Raise the Python exception for this error with the given message, so
+	/// wrapping variants keep the exception type of the error they wrap.
+	fn raise(self, msg: String) -> PyErr {
+		match self {
+			/* The callback's own exception is the cause, so Python shows its
+			 * traceback too. */
+			Fatal::BadCallback(err) => Python::with_gil(|py| {
+				let wrapped = BadCallbackError::new_err(msg);
+				wrapped.set_cause(py, Some(err));
+				wrapped
+			}),
+			Fatal::TimedOut { .. } => CallbackTimeoutError::new_err(msg),
+			Fatal::MathFailed { source, .. } => Python::with_gil(|py| {
+				let wrapped = CannotRenderMathError::new_err(msg);
+				wrapped.set_cause(py, Some(source));
+				wrapped
+			}),
+			Fatal::Unrepresentable(_) => PulldownCmarkError::new_err(msg),
+			Fatal::TooLarge { .. } | Fatal::TooDeep { .. } => PyValueError::new_err(msg),
+			Fatal::BadTable { .. } => BadTableError::new_err(msg),
+			Fatal::BadFrontmatter { .. } => BadFrontmatterError::new_err(msg),
+			/* `OSError` picks the subclass for the errno, e.g. `FileNotFoundError`. */
+			Fatal::Read { path, source } => match source.raw_os_error() {
+				Some(errno) => PyOSError::new_err((errno, msg, path)),
+				None => PyOSError::new_err(msg),
+			},
+			Fatal::AtInput { source, .. } | Fatal::AtKey { source, .. } => source.raise(msg),
+		}
+	}
 }
 
 create_exception!(pulldown_cmark, PulldownCmarkError, PyException);
 create_exception!(pulldown_cmark, BadCallbackError, PulldownCmarkError);
+create_exception!(pulldown_cmark, CannotRenderMathError, BadCallbackError);
+create_exception!(pulldown_cmark, CallbackTimeoutError, BadCallbackError);
+create_exception!(pulldown_cmark, CannotConfigMathError, PulldownCmarkError);
+create_exception!(pulldown_cmark, BadTableError, PulldownCmarkError);
+create_exception!(pulldown_cmark, BadFrontmatterError, PulldownCmarkError);
 
 impl From<Fatal> for PyErr {
 	fn from(err: Fatal) -> PyErr {
 		let msg = err.to_string();
-		match err {
-			Fatal::BadCallback { .. } => BadCallbackError::new_err(msg),
-		}
+		err.raise(msg)
 	}
 }
@@ -0,0 +1,271 @@
+use crate::options::PyOptions;
+use ::pulldown_cmark::{
+	Alignment, BlockQuoteKind, CodeBlockKind, Event, LinkType, MetadataBlockKind, Parser, Tag, TagEnd,
+};
+use pyo3::{
+	IntoPyObjectExt,
+	prelude::*,
+	types::{PyDict, PyList},
+};
+use serde_json::{Map, Value, json};
+
+/// Parse a Markdown string into a list of `pulldown-cmark` events, for custom
+/// renderers.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     The Markdown string to parse.
+/// options
+///     The Markdown extensions to enable. Callbacks are ignored.
+///
+/// Returns
+/// -------
+/// A list of dicts in document order, each with a `type` key:
+///
+/// - `start` and `end` events open and close a `tag`, such as `paragraph` or
+///   `heading`. Start events carry the tag's fields, e.g. `level`, `id`,
+///   `classes`, and `attrs` (a list of `[name, value]` pairs) for headings,
+///   or `link_type`, `dest_url`, `title`, and `id` for links and images.
+/// - `text`, `code`, `inline_math`, `display_math`, `html`, `inline_html`, and
+///   `footnote_reference` events carry a `value` string.
+/// - `task_list_marker` events carry a `checked` bool.
+/// - `soft_break`, `hard_break`, and `rule` events carry nothing else.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None))]
+pub fn parse_events<'py>(
+	py: Python<'py>,
+	markdown: &str,
+	options: Option<&PyOptions>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+
+	Parser::new_ext(markdown, options.flags)
+		.map(|event| to_dict(py, &to_object(event)))
+		.collect()
+}
+
+/// Convert an event into a JSON object, as described by `parse_events`.
+pub fn to_object(event: Event) -> Map<String, Value> {
+	let mut object = Map::new();
+
+	let (kind, value) = match event {
+		Event::Start(tag) => {
+			object.insert(String::from("type"), Value::from("start"));
+			object.insert(String::from("tag"), Value::from(name(&tag.to_end())));
+			object.extend(fields(tag));
+			return object;
+		}
+
+		Event::End(end) => {
+			object.insert(String::from("type"), Value::from("end"));
+			object.insert(String::from("tag"), Value::from(name(&end)));
+			return object;
+		}
+
+		Event::TaskListMarker(checked) => {
+			object.insert(String::from("type"), Value::from("task_list_marker"));
+			object.insert(String::from("checked"), Value::from(checked));
+			return object;
+		}
+
+		Event::Text(value) => ("text", Some(value)),
+		Event::Code(value) => ("code", Some(value)),
+		Event::InlineMath(value) => ("inline_math", Some(value)),
+		Event::DisplayMath(value) => ("display_math", Some(value)),
+		Event::Html(value) => ("html", Some(value)),
+		Event::InlineHtml(value) => ("inline_html", Some(value)),
+		Event::FootnoteReference(value) => ("footnote_reference", Some(value)),
+		Event::SoftBreak => ("soft_break", None),
+		Event::HardBreak => ("hard_break", None),
+		Event::Rule => ("rule", None),
+	};
+
+	object.insert(String::from("type"), Value::from(kind));
+
+	if let Some(value) = value {
+		object.insert(String::from("value"), Value::from(value.as_ref()));
+	}
+
+	object
+}
+
+/// The fields of a start tag.
+pub fn fields(tag: Tag) -> Map<String, Value> {
+	let mut fields = Map::new();
+	let mut set = |name: &str, value: Value| fields.insert(String::from(name), value);
+
+	match tag {
+		Tag::Heading {
+			level,
+			id,
+			classes,
+			attrs,
+		} => {
+			let classes: Vec<&str> = classes.iter().map(AsRef::as_ref).collect();
+			let attrs: Vec<Value> = attrs
+				.iter()
+				.map(|(name, value)| json!([name.as_ref(), value.as_deref()]))
+				.collect();
+
+			set("level", Value::from(level as u8));
+			set("id", Value::from(id.as_deref()));
+			set("classes", Value::from(classes));
+			set("attrs", Value::from(attrs));
+		}
+
+		Tag::BlockQuote(kind) => {
+			set("kind", Value::from(kind.map(alert)));
+		}
+
+		Tag::CodeBlock(kind) => match kind {
+			CodeBlockKind::Fenced(info) => {
+				set("fenced", Value::from(true));
+				set("info", Value::from(info.as_ref()));
+			}
+
+			CodeBlockKind::Indented => {
+				set("fenced", Value::from(false));
+				set("info", Value::Null);
+			}
+		},
+
+		Tag::List(start) => {
+			set("start", Value::from(start));
+		}
+
+		Tag::FootnoteDefinition(label) => {
+			set("label", Value::from(label.as_ref()));
+		}
+
+		Tag::Table(alignments) => {
+			let alignments: Vec<&str> = alignments
+				.iter()
+				.map(|alignment| match alignment {
+					Alignment::None => "none",
+					Alignment::Left => "left",
+					Alignment::Center => "center",
+					Alignment::Right => "right",
+				})
+				.collect();
+
+			set("alignments", Value::from(alignments));
+		}
+
+		Tag::Link {
+			link_type,
+			dest_url,
+			title,
+			id,
+		}
+		| Tag::Image {
+			link_type,
+			dest_url,
+			title,
+			id,
+		} => {
+			let link_type = match link_type {
+				LinkType::Inline => "inline",
+				LinkType::Reference => "reference",
+				LinkType::ReferenceUnknown => "reference_unknown",
+				LinkType::Collapsed => "collapsed",
+				LinkType::CollapsedUnknown => "collapsed_unknown",
+				LinkType::Shortcut => "shortcut",
+				LinkType::ShortcutUnknown => "shortcut_unknown",
+				LinkType::Autolink => "autolink",
+				LinkType::Email => "email",
+				LinkType::WikiLink { .. } => "wikilink",
+			};
+
+			set("link_type", Value::from(link_type));
+			set("dest_url", Value::from(dest_url.as_ref()));
+			set("title", Value::from(title.as_ref()));
+			set("id", Value::from(id.as_ref()));
+		}
+
+		Tag::MetadataBlock(kind) => {
+			let kind = match kind {
+				MetadataBlockKind::YamlStyle => "yaml",
+				MetadataBlockKind::PlusesStyle => "pluses",
+			};
+
+			set("kind", Value::from(kind));
+		}
+
+		_ => {}
+	}
+
+	fields
+}
+
+/// Convert a JSON value into the equivalent Python object.
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+	Ok(match value {
+		Value::Null => py.None().into_bound(py),
+		Value::Bool(value) => value.into_bound_py_any(py)?,
+		Value::Number(number) => match number.as_u64() {
+			Some(number) => number.into_bound_py_any(py)?,
+			None => number.as_f64().into_bound_py_any(py)?,
+		},
+		Value::String(value) => value.into_bound_py_any(py)?,
+		Value::Array(values) => {
+			let values: Vec<_> = values
+				.iter()
+				.map(|value| to_python(py, value))
+				.collect::<PyResult<_>>()?;
+			PyList::new(py, values)?.into_any()
+		}
+		Value::Object(object) => to_dict(py, object)?.into_any(),
+	})
+}
+
+fn to_dict<'py>(py: Python<'py>, object: &Map<String, Value>) -> PyResult<Bound<'py, PyDict>> {
+	let dict = PyDict::new(py);
+
+	for (key, value) in object {
+		dict.set_item(key, to_python(py, value)?)?;
+	}
+
+	Ok(dict)
+}
+
+/// The name of a GFM alert kind, e.g. `note` for `[!NOTE]`.
+pub fn alert(kind: BlockQuoteKind) -> &'static str {
+	match kind {
+		BlockQuoteKind::Note => "note",
+		BlockQuoteKind::Tip => "tip",
+		BlockQuoteKind::Important => "important",
+		BlockQuoteKind::Warning => "warning",
+		BlockQuoteKind::Caution => "caution",
+	}
+}
+
+/// The `tag` name of an event, which start and end events share.
+pub fn name(end: &TagEnd) -> &'static str {
+	match end {
+		TagEnd::Paragraph => "paragraph",
+		TagEnd::Heading(_) => "heading",
+		TagEnd::BlockQuote(_) => "block_quote",
+		TagEnd::CodeBlock => "code_block",
+		TagEnd::HtmlBlock => "html_block",
+		TagEnd::List(_) => "list",
+		TagEnd::Item => "item",
+		TagEnd::FootnoteDefinition => "footnote_definition",
+		TagEnd::DefinitionList => "definition_list",
+		TagEnd::DefinitionListTitle => "definition_list_title",
+		TagEnd::DefinitionListDefinition => "definition_list_definition",
+		TagEnd::Table => "table",
+		TagEnd::TableHead => "table_head",
+		TagEnd::TableRow => "table_row",
+		TagEnd::TableCell => "table_cell",
+		TagEnd::Emphasis => "emphasis",
+		TagEnd::Strong => "strong",
+		TagEnd::Strikethrough => "strikethrough",
+		TagEnd::Superscript => "superscript",
+		TagEnd::Subscript => "subscript",
+		TagEnd::Link => "link",
+		TagEnd::Image => "image",
+		TagEnd::MetadataBlock(_) => "metadata_block",
+	}
+}
@@ -0,0 +1,179 @@
+use crate::emoji::emojify;
+use crate::inputs;
+use crate::iter::shift;
+use crate::options::PyOptions;
+use crate::quotes::{Quotes, localize, quotes};
+use crate::slug::Slugger;
+use ::pulldown_cmark::{CowStr, Event, LinkType, Parser, Tag, TagEnd};
+use pyo3::{IntoPyObjectExt, prelude::*};
+use rayon::prelude::*;
+use std::mem::take;
+use std::ops::Range;
+
+/// Extract the table of contents of a Markdown string.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     The Markdown string to scan for headings.
+/// options
+///     The Markdown extensions to enable.
+/// with_offsets
+///     Whether to add the `start` and `end` byte offsets of each heading in the
+///     UTF-8 encoding of `markdown` to its tuple.
+///
+/// Returns
+/// -------
+/// A list of `(level, text, slug)` tuples in document order, where `level` is
+/// the rendered level, after `heading_offset`, `text` is the heading with
+/// inline formatting flattened, and with `emoji` and `smart_quotes_locale`
+/// applied as in rendered HTML, and `slug` is its
+/// explicit ID if `heading_attributes` gave it one, or else its GitHub-style
+/// anchor, the same as its `id` in rendered HTML. With `with_offsets`, the
+/// tuples are `(level, text, slug, start, end)`.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None, *, with_offsets = false))]
+pub fn extract_toc(py: Python, markdown: &str, options: Option<&PyOptions>, with_offsets: bool) -> PyResult<PyObject> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+
+	let mut slugger = Slugger::new(options.settings.slug_style.as_deref());
+	let quotes = options.settings.smart_quotes_locale.as_deref().and_then(quotes);
+	let mut headings = Vec::new();
+	let mut current: Option<(u8, Option<CowStr>, String, Range<usize>)> = None;
+	/* Text is rewritten a run at a time, as `render` does before slugging it,
+	 * as the parser splits it at shortcodes and quotes. */
+	let mut run = String::new();
+	let mut previous = ' ';
+
+	for (event, range) in Parser::new_ext(markdown, options.flags).into_offset_iter() {
+		if let (Some((_, _, buffer, _)), false) = (&mut current, matches!(event, Event::Text(_))) {
+			buffer.push_str(&heading_text(&take(&mut run), options, quotes.as_ref(), &mut previous));
+		}
+
+		match event {
+			Event::Start(Tag::Heading { level, id, .. }) => {
+				current = Some((shift(level, options.settings.heading_offset) as u8, id, String::new(), range));
+				previous = ' ';
+			}
+
+			Event::Text(text) if current.is_some() => run.push_str(&text),
+
+			Event::Code(text) => {
+				if let Some((_, _, buffer, _)) = &mut current {
+					buffer.push_str(&text);
+					previous = text.chars().last().unwrap_or(previous);
+				}
+			}
+
+			Event::End(TagEnd::Heading(_)) => {
+				if let Some((level, id, text, range)) = current.take() {
+					let slug = slugger.anchor(id.as_deref(), &text);
+					headings.push((level, text, slug, range));
+				}
+			}
+
+			_ => {}
+		}
+	}
+
+	let headings = headings.into_iter();
+
+	match with_offsets {
+		true => headings
+			.map(|(level, text, slug, range)| (level, text, slug, range.start, range.end))
+			.collect::<Vec<_>>()
+			.into_py_any(py),
+		false => headings
+			.map(|(level, text, slug, _)| (level, text, slug))
+			.collect::<Vec<_>>()
+			.into_py_any(py),
+	}
+}
+
+/// Rewrite a run of heading text per `emoji` and `smart_quotes_locale`, where
+/// `previous` is the character before it.
+fn heading_text(run: &str, options: &PyOptions, quotes: Option<&Quotes>, previous: &mut char) -> String {
+	let settings = &options.settings;
+
+	let text = match settings.emoji {
+		true => emojify(run, settings.emoji_map.as_ref()),
+		false => run.into(),
+	};
+
+	match quotes {
+		Some(quotes) if !text.is_empty() => localize(&text, quotes, previous),
+		_ => text.into_owned(),
+	}
+}
+
+/// Extract the link destinations of a list of Markdown strings.
+///
+/// Inline, reference-style, and autolinks are all included, as are wikilinks
+/// if `wikilinks` is enabled. Email autolinks are given a `mailto:` prefix, as
+/// in rendered HTML. Images are not links, so they are skipped.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     An iterable of Markdown `str` or UTF-8 `bytes` to scan for links.
+/// options
+///     The Markdown extensions to enable.
+/// with_offsets
+///     Whether to return each link as a `(destination, start, end)` tuple, with
+///     the byte offsets of the link in the UTF-8 encoding of its input.
+///
+/// Returns
+/// -------
+/// A list which preserves the indices of `markdown`, holding for each input
+/// the list of its link destinations in document order, with duplicates.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None, *, with_offsets = false))]
+pub fn extract_links(
+	py: Python,
+	markdown: &Bound<'_, PyAny>,
+	options: Option<&PyOptions>,
+	with_offsets: bool,
+) -> PyResult<PyObject> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let inputs = inputs(markdown)?;
+
+	let links: Vec<Vec<(String, Range<usize>)>> = py.allow_threads(move || {
+		inputs.par_iter()
+			.map(|buffer| {
+				Parser::new_ext(buffer, options.flags)
+					.into_offset_iter()
+					.filter_map(|(event, range)| match event {
+						Event::Start(Tag::Link {
+							link_type: LinkType::Email,
+							dest_url,
+							..
+						}) => Some((format!("mailto:{dest_url}"), range)),
+						Event::Start(Tag::Link { dest_url, .. }) => {
+							Some((String::from(dest_url), range))
+						}
+						_ => None,
+					})
+					.collect()
+			})
+			.collect()
+	});
+
+	match with_offsets {
+		true => links
+			.into_iter()
+			.map(|links| {
+				links.into_iter()
+					.map(|(url, range)| (url, range.start, range.end))
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>()
+			.into_py_any(py),
+		false => links
+			.into_iter()
+			.map(|links| links.into_iter().map(|(url, _)| url).collect::<Vec<_>>())
+			.collect::<Vec<_>>()
+			.into_py_any(py),
+	}
+}
@@ -0,0 +1,82 @@
+/// Split a code fence info string into its language and a trailing
+/// Pandoc-style attribute block, e.g. `python {.numberLines startFrom="5"}`.
+///
+/// The language is the first word of the info string, up to a space or comma,
+/// e.g. `rust` for `rust,ignore` or `python title="app.py"`. Classes are joined
+/// into one `class` attribute, `#foo` gives `id`, and a key without a value
+/// maps to an empty string. An info string without a `{` has no attributes,
+/// nor does one with an unclosed block.
+pub fn split_info(info: &str) -> (Option<&str>, Vec<(String, String)>) {
+	let (before, attrs) = match info.split_once('{') {
+		Some((before, rest)) if rest.trim_end().ends_with('}') => {
+			(before, parse(rest.trim_end().trim_end_matches('}')))
+		}
+
+		_ => (info, Vec::new()),
+	};
+
+	let language = before
+		.trim_start()
+		.split(|c: char| c.is_whitespace() || c == ',')
+		.next();
+	(language.filter(|language| !language.is_empty()), attrs)
+}
+
+/// Parse the inside of an attribute block.
+fn parse(block: &str) -> Vec<(String, String)> {
+	let mut attrs = Vec::new();
+	let mut classes = Vec::new();
+
+	for token in tokens(block) {
+		if let Some(class) = token.strip_prefix('.') {
+			classes.push(class.to_owned());
+		} else if let Some(id) = token.strip_prefix('#') {
+			attrs.push((String::from("id"), id.to_owned()));
+		} else {
+			let (key, value) = token.split_once('=').unwrap_or((token.as_str(), ""));
+			attrs.push((key.to_owned(), unquote(value).to_owned()));
+		}
+	}
+
+	if !classes.is_empty() {
+		attrs.insert(0, (String::from("class"), classes.join(" ")));
+	}
+
+	attrs
+}
+
+/// Split on whitespace outside double quotes.
+fn tokens(block: &str) -> Vec<String> {
+	let mut tokens = Vec::new();
+	let mut token = String::new();
+	let mut quoted = false;
+
+	for c in block.chars() {
+		match c {
+			'"' => {
+				quoted = !quoted;
+				token.push(c);
+			}
+
+			c if c.is_whitespace() && !quoted => {
+				if !token.is_empty() {
+					tokens.push(std::mem::take(&mut token));
+				}
+			}
+
+			c => token.push(c),
+		}
+	}
+
+	if !token.is_empty() {
+		tokens.push(token);
+	}
+
+	tokens
+}
+
+fn unquote(value: &str) -> &str {
+	value.strip_prefix('"')
+		.and_then(|value| value.strip_suffix('"'))
+		.unwrap_or(value)
+}
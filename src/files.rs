@@ -0,0 +1,68 @@
+use crate::error::Fatal;
+use crate::options::PyOptions;
+use crate::{check_context, markdown_iter, render_one};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+/// Read and render a list of Markdown files into a list of HTML strings.
+///
+/// Files are read and rendered in parallel, without holding the GIL, so their
+/// contents never reach the Python heap.
+///
+/// Parameters
+/// ----------
+/// paths
+///     An iterable of paths, as `str` or `os.PathLike`, to UTF-8 Markdown
+///     files.
+/// options
+///     The Markdown extensions to enable.
+/// context
+///     A list with an item for each path, which is passed to callbacks as in
+///     `render`.
+///
+/// Returns
+/// -------
+/// A list of HTML strings which preserves the indices of `paths`.
+///
+/// Raises
+/// ------
+/// OSError
+///    If a file can't be read, with its path as `filename`.
+/// BadCallbackError
+///    If a user callback fails while Markdown is parsed.
+/// ValueError
+///    If `context` doesn't have an item for each path.
+#[pyfunction]
+#[pyo3(signature = (paths, options = None, context = None))]
+pub fn render_files(
+	py: Python,
+	paths: &Bound<'_, PyAny>,
+	options: Option<&PyOptions>,
+	context: Option<Vec<PyObject>>,
+) -> PyResult<Vec<String>> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let paths = markdown_iter(paths)?
+		.map(|path| path?.extract())
+		.collect::<PyResult<Vec<PathBuf>>>()?;
+	check_context(context.as_ref(), paths.len())?;
+
+	py.allow_threads(move || {
+		paths.par_iter()
+			.enumerate()
+			.map(|(index, path)| {
+				let buffer = read_to_string(path).map_err(|source| Fatal::Read {
+					path: path.clone(),
+					source,
+				});
+
+				let context = context.as_ref().map(|context| &context[index]);
+				buffer.and_then(|buffer| render_one(&buffer, options, context))
+					.map_err(|err| err.at(index))
+			})
+			.collect::<Result<Vec<String>, Fatal>>()
+			.map_err(PyErr::from)
+	})
+}
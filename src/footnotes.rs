@@ -0,0 +1,206 @@
+use crate::html::StartTag;
+use crate::options::Settings;
+use ::pulldown_cmark::{Event, TagEnd};
+use pulldown_cmark_escape::escape_html;
+use std::collections::HashMap;
+use std::mem::take;
+
+/// Footnotes collected by `collect_footnotes`, to be rendered in one section at
+/// the end of the document, as GitHub does.
+#[derive(Default)]
+pub struct Footnotes<'p> {
+	/// The `footnote_prefix` of each ID.
+	prefix: String,
+	/// Whether to add `accessible` roles and descriptions.
+	accessible: bool,
+	/// The heading of the section.
+	title: String,
+	/// The labels of referenced footnotes, in order of first reference.
+	order: Vec<String>,
+	/// The number and reference count of each referenced label.
+	references: HashMap<String, (usize, usize)>,
+	/// The events inside each footnote definition.
+	definitions: HashMap<String, Vec<Event<'p>>>,
+	/// The number of each label seen outside `collect_footnotes`, as
+	/// `push_html` numbers them.
+	numbers: HashMap<String, usize>,
+}
+
+impl<'p> Footnotes<'p> {
+	pub fn new(settings: &Settings) -> Self {
+		Self {
+			prefix: settings.footnote_prefix.clone().unwrap_or_default(),
+			accessible: settings.accessible,
+			title: settings.footnotes_title.as_deref().unwrap_or("Footnotes").to_owned(),
+			..Self::default()
+		}
+	}
+
+	/// Render a reference to `label`, numbered in order of first reference.
+	pub fn reference(&mut self, label: &str) -> Event<'p> {
+		let next = self.order.len() + 1;
+
+		let (number, count) = self.references.entry(label.to_owned()).or_insert_with(|| {
+			self.order.push(label.to_owned());
+			(next, 0)
+		});
+
+		*count += 1;
+
+		let mut html = StartTag::new("a")
+			.url("href", &format!("#{}fn-{label}", self.prefix))
+			.attr("id", &reference_id(&self.prefix, label, *count));
+
+		if self.accessible {
+			html = html
+				.attr("role", "doc-noteref")
+				.attr("aria-describedby", &format!("{}footnote-label", self.prefix));
+		}
+
+		let html = html.open();
+
+		Event::InlineHtml(format!("<sup class=\"footnote-ref\">{html}{number}</a></sup>").into())
+	}
+
+	/// Render a reference to `label` in place, as `push_html` does, but as a
+	/// `doc-noteref` described by its definition.
+	pub fn inline_reference(&mut self, label: &str) -> Event<'p> {
+		let html = StartTag::new("a")
+			.attr("href", &format!("#{label}"))
+			.attr("role", "doc-noteref")
+			.attr("aria-describedby", label)
+			.open();
+
+		let number = self.number(label);
+
+		Event::InlineHtml(format!("<sup class=\"footnote-reference\">{html}{number}</a></sup>").into())
+	}
+
+	/// Render the start of the definition of `label` in place, as `push_html`
+	/// does, but as a `doc-footnote`.
+	pub fn inline_definition(&mut self, label: &str) -> String {
+		let html = StartTag::new("div")
+			.attr("class", "footnote-definition")
+			.attr("id", label)
+			.attr("role", "doc-footnote")
+			.open();
+
+		let number = self.number(label);
+
+		format!("{html}<sup class=\"footnote-definition-label\">{number}</sup>")
+	}
+
+	/// The number of `label`, in order of first reference or definition.
+	fn number(&mut self, label: &str) -> usize {
+		let next = self.numbers.len() + 1;
+		*self.numbers.entry(label.to_owned()).or_insert(next)
+	}
+
+	/// Keep the events of a definition for the section. The first definition of
+	/// a label wins.
+	pub fn define(&mut self, label: String, events: Vec<Event<'p>>) {
+		self.definitions.entry(label).or_insert(events);
+	}
+
+	/// The footnotes section, with each definition followed by a back-reference
+	/// to each of its references, or nothing if no footnote was referenced.
+	/// Unreferenced and undefined footnotes are left out.
+	pub fn section(&mut self) -> Vec<Event<'p>> {
+		let mut events = Vec::new();
+
+		for label in take(&mut self.order) {
+			let (Some(mut definition), Some(&(_, count))) =
+				(self.definitions.remove(&label), self.references.get(&label))
+			else {
+				continue;
+			};
+
+			let backrefs: Vec<String> = (1..=count)
+				.map(|index| backref(&self.prefix, &label, index, self.accessible))
+				.collect();
+			let backrefs = backrefs.join(" ");
+
+			/* Back-references go inside the last paragraph, if it ends the
+			 * definition, rather than on a line of their own. */
+			match definition.last() {
+				Some(Event::End(TagEnd::Paragraph)) => {
+					definition.insert(
+						definition.len() - 1,
+						Event::InlineHtml(format!(" {backrefs}").into()),
+					);
+				}
+
+				_ => definition.push(Event::Html(format!("<p>{backrefs}</p>\n").into())),
+			}
+
+			events.push(Event::Html(
+				format!(
+					"{}\n",
+					StartTag::new("li")
+						.attr("id", &format!("{}fn-{label}", self.prefix))
+						.open()
+				)
+				.into(),
+			));
+			events.extend(definition);
+			events.push(Event::Html("</li>\n".into()));
+		}
+
+		if events.is_empty() {
+			return events;
+		}
+
+		let heading = StartTag::new("h2")
+			.attr("id", &format!("{}footnote-label", self.prefix))
+			.attr("class", "sr-only")
+			.open();
+
+		let role = if self.accessible { " role=\"doc-endnotes\"" } else { "" };
+		let mut title = String::new();
+		/* Writing to a `String` can't fail. */
+		escape_html(&mut title, &self.title).unwrap();
+
+		events.insert(
+			0,
+			Event::Html(
+				format!(
+					"<section class=\"footnotes\" data-footnotes{role}>\n{heading}{title}</h2>\n<ol>\n"
+				)
+				.into(),
+			),
+		);
+
+		events.push(Event::Html("</ol>\n</section>\n".into()));
+		events
+	}
+}
+
+/// The ID of the `index`th reference to `label`, counting from 1.
+fn reference_id(prefix: &str, label: &str, index: usize) -> String {
+	match index {
+		1 => format!("{prefix}fnref-{label}"),
+		_ => format!("{prefix}fnref-{label}-{index}"),
+	}
+}
+
+/// A link back to the `index`th reference to `label`, as a `doc-backlink` if
+/// `accessible`.
+fn backref(prefix: &str, label: &str, index: usize, accessible: bool) -> String {
+	let marker = match index {
+		1 => String::from("↩"),
+		_ => format!("↩<sup>{index}</sup>"),
+	};
+
+	let mut html = StartTag::new("a")
+		.url("href", &format!("#{}", reference_id(prefix, label, index)))
+		.attr("class", "footnote-backref")
+		.attr("aria-label", &format!("Back to reference {index}"));
+
+	if accessible {
+		html = html.attr("role", "doc-backlink");
+	}
+
+	let html = html.open();
+
+	format!("{html}{marker}</a>")
+}
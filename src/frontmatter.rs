@@ -0,0 +1,170 @@
+use crate::error::Fatal;
+use pyo3::{IntoPyObjectExt, prelude::*, types::PyDict};
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// Split a leading front matter block from the rest of a Markdown string.
+///
+/// A block opens with a `---` (YAML) or `+++` (TOML) line. It closes with the
+/// next `---` or `...` line for YAML, or `+++` line for TOML; an unclosed
+/// block is not front matter.
+pub fn split(markdown: &str) -> Option<(&str, &str)> {
+	let mut lines = markdown.split_inclusive('\n');
+
+	let first = lines.next()?;
+	let closers: &[&str] = match first.trim_end() {
+		"---" => &["---", "..."],
+		"+++" => &["+++"],
+		_ => return None,
+	};
+
+	let start = first.len();
+	let mut offset = start;
+
+	for line in lines {
+		if closers.contains(&line.trim_end()) {
+			return Some((&markdown[start..offset], &markdown[offset + line.len()..]));
+		}
+
+		offset += line.len();
+	}
+
+	None
+}
+
+/// Split the front matter from a Markdown string, without parsing it.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     The Markdown string to split.
+///
+/// Returns
+/// -------
+/// A `(front_matter, body)` tuple, where `front_matter` is the raw text
+/// between the delimiter lines of a leading `---` or `+++` block, or `None` if
+/// there is no such block, in which case `body` is `markdown` unchanged.
+#[pyfunction]
+pub fn split_frontmatter(markdown: &str) -> (Option<&str>, &str) {
+	match split(markdown) {
+		Some((frontmatter, body)) => (Some(frontmatter), body),
+		None => (None, markdown),
+	}
+}
+
+/// Parse the front matter of a Markdown string into a dict.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     The Markdown string to parse.
+///
+/// Returns
+/// -------
+/// A `(front_matter, body)` tuple, where `front_matter` is the mapping of a
+/// leading `---` (YAML) or `+++` (TOML) block, as split by `split_frontmatter`,
+/// or `None` if there is no such block. An empty block is an empty dict. TOML
+/// dates and times are ISO 8601 strings.
+///
+/// Raises
+/// ------
+/// BadFrontmatterError
+///    If the front matter isn't valid YAML or TOML, or isn't a mapping.
+#[pyfunction]
+pub fn parse_frontmatter(py: Python, markdown: &str) -> PyResult<(Option<Py<PyDict>>, String)> {
+	let Some((frontmatter, body)) = split(markdown) else {
+		return Ok((None, markdown.to_owned()));
+	};
+
+	let dict = match markdown.starts_with('+') {
+		true => {
+			let table = toml::from_str(frontmatter).map_err(|err| bad_frontmatter("TOML", err))?;
+			toml_to_py(py, toml::Value::Table(table))?
+		}
+
+		false => {
+			let documents =
+				YamlLoader::load_from_str(frontmatter).map_err(|err| bad_frontmatter("YAML", err))?;
+
+			match <[Yaml; 1]>::try_from(documents) {
+				Ok([value @ Yaml::Hash(_)]) => yaml_to_py(py, value)?,
+				Ok([Yaml::Null]) => PyDict::new(py).into_py_any(py)?,
+				Ok(_) => return Err(bad_frontmatter("YAML", "not a mapping").into()),
+				/* An empty block has no document. */
+				Err(documents) if documents.is_empty() => PyDict::new(py).into_py_any(py)?,
+				Err(_) => return Err(bad_frontmatter("YAML", "more than one document").into()),
+			}
+		}
+	};
+
+	Ok((Some(dict.extract(py)?), body.to_owned()))
+}
+
+fn bad_frontmatter(format: &'static str, err: impl ToString) -> Fatal {
+	Fatal::BadFrontmatter {
+		format,
+		message: err.to_string(),
+	}
+}
+
+/// Convert a YAML value into a Python object, dropping any tags.
+fn yaml_to_py(py: Python, value: Yaml) -> PyResult<PyObject> {
+	match value {
+		/* Aliases are resolved by the loader, and a bad value is one a tag
+		 * couldn't convert, e.g. `!!int foo`. */
+		Yaml::Null | Yaml::Alias(_) | Yaml::BadValue => Ok(py.None()),
+		Yaml::Boolean(value) => value.into_py_any(py),
+		Yaml::Integer(value) => value.into_py_any(py),
+		/* A real is kept as its source, as is an integer past `i64`. */
+		Yaml::Real(value) => match value.parse::<u64>() {
+			Ok(number) => number.into_py_any(py),
+			Err(_) => Yaml::Real(value).as_f64().into_py_any(py),
+		},
+		Yaml::String(value) => value.into_py_any(py),
+		Yaml::Array(values) => values
+			.into_iter()
+			.map(|value| yaml_to_py(py, value))
+			.collect::<PyResult<Vec<_>>>()?
+			.into_py_any(py),
+		Yaml::Hash(hash) => {
+			let dict = PyDict::new(py);
+
+			for (key, value) in hash {
+				/* A sequence or mapping would be an unhashable `dict` key. */
+				if let Yaml::Array(_) | Yaml::Hash(_) = key {
+					return Err(bad_frontmatter("YAML", "a mapping key is not a scalar").into());
+				}
+
+				dict.set_item(yaml_to_py(py, key)?, yaml_to_py(py, value)?)?;
+			}
+
+			dict.into_py_any(py)
+		}
+	}
+}
+
+/// Convert a TOML value into a Python object.
+fn toml_to_py(py: Python, value: toml::Value) -> PyResult<PyObject> {
+	use toml::Value;
+
+	match value {
+		Value::String(value) => value.into_py_any(py),
+		Value::Integer(value) => value.into_py_any(py),
+		Value::Float(value) => value.into_py_any(py),
+		Value::Boolean(value) => value.into_py_any(py),
+		Value::Datetime(value) => value.to_string().into_py_any(py),
+		Value::Array(values) => values
+			.into_iter()
+			.map(|value| toml_to_py(py, value))
+			.collect::<PyResult<Vec<_>>>()?
+			.into_py_any(py),
+		Value::Table(table) => {
+			let dict = PyDict::new(py);
+
+			for (key, value) in table {
+				dict.set_item(key, toml_to_py(py, value)?)?;
+			}
+
+			dict.into_py_any(py)
+		}
+	}
+}
@@ -0,0 +1,241 @@
+use ::pulldown_cmark::{Event, Tag, TagEnd};
+use pulldown_cmark_escape::{escape_href, escape_html};
+use std::borrow::Cow;
+
+/// An HTML start tag, for events `push_html` can't render with the markup we
+/// need, built up attribute by attribute.
+pub struct StartTag {
+	html: String,
+}
+
+impl StartTag {
+	pub fn new(name: &str) -> Self {
+		Self {
+			html: format!("<{name}"),
+		}
+	}
+
+	/// Add a URL attribute, escaped as `push_html` escapes link destinations.
+	pub fn url(mut self, name: &str, url: &str) -> Self {
+		self.html.push_str(&format!(" {name}=\""));
+		/* Writing to a `String` can't fail. */
+		escape_href(&mut self.html, url).unwrap();
+		self.html.push('"');
+		self
+	}
+
+	/// Add an attribute, escaping its value.
+	pub fn attr(mut self, name: &str, value: &str) -> Self {
+		self.html.push_str(&format!(" {name}=\""));
+		escape_html(&mut self.html, value).unwrap();
+		self.html.push('"');
+		self
+	}
+
+	/// Add an attribute if it has a non-empty value.
+	pub fn attr_if(self, name: &str, value: &str) -> Self {
+		match value.is_empty() {
+			true => self,
+			false => self.attr(name, value),
+		}
+	}
+
+	/// Close the tag as an element with content.
+	pub fn open(mut self) -> String {
+		self.html.push('>');
+		self.html
+	}
+
+	/// Close the tag as a void element, XHTML-style as `push_html` does.
+	pub fn void(mut self) -> String {
+		self.html.push_str(" />");
+		self.html
+	}
+}
+
+/// Remove HTML comments from a chunk of raw HTML. A comment may span chunks, so
+/// `in_comment` tracks whether the previous chunk ended inside one.
+pub fn strip_comments<'a>(html: &'a str, in_comment: &mut bool) -> Cow<'a, str> {
+	if !*in_comment && !html.contains("<!--") {
+		return Cow::Borrowed(html);
+	}
+
+	let mut output = String::new();
+	let mut rest = html;
+
+	loop {
+		if *in_comment {
+			let Some(end) = rest.find("-->") else {
+				break;
+			};
+
+			rest = &rest[end + 3..];
+			*in_comment = false;
+		}
+
+		let Some(start) = rest.find("<!--") else {
+			output.push_str(rest);
+			break;
+		};
+
+		output.push_str(&rest[..start]);
+		rest = &rest[start + 4..];
+		*in_comment = true;
+	}
+
+	Cow::Owned(output)
+}
+
+/// Whether the output of `push_html` ends a line after it renders `event`, or
+/// `None` if `event` writes nothing. Block elements start on a new line, so raw
+/// HTML standing in for one must too.
+pub fn ends_line(event: &Event) -> Option<bool> {
+	Some(match event {
+		Event::Start(Tag::HtmlBlock | Tag::MetadataBlock(_))
+		| Event::End(TagEnd::HtmlBlock | TagEnd::MetadataBlock(_)) => {
+			return None;
+		}
+
+		Event::Html(html) | Event::InlineHtml(html) if html.is_empty() => return None,
+		Event::Html(html) | Event::InlineHtml(html) => html.ends_with('\n'),
+		Event::Text(text) => text.ends_with('\n'),
+		Event::Start(Tag::BlockQuote(_) | Tag::List(_) | Tag::DefinitionList) => true,
+		Event::Start(_) => false,
+
+		Event::End(
+			TagEnd::TableCell
+			| TagEnd::Emphasis
+			| TagEnd::Strong
+			| TagEnd::Strikethrough
+			| TagEnd::Superscript
+			| TagEnd::Subscript
+			| TagEnd::Link
+			| TagEnd::Image,
+		) => false,
+
+		Event::End(_) => true,
+		Event::SoftBreak | Event::HardBreak | Event::Rule | Event::TaskListMarker(_) => true,
+		Event::Code(_) | Event::InlineMath(_) | Event::DisplayMath(_) | Event::FootnoteReference(_) => false,
+	})
+}
+
+/// Block-level elements, around which whitespace between tags is insignificant.
+const BLOCK_TAGS: &[&str] = &[
+	"address",
+	"article",
+	"aside",
+	"blockquote",
+	"dd",
+	"details",
+	"div",
+	"dl",
+	"dt",
+	"figcaption",
+	"figure",
+	"footer",
+	"h1",
+	"h2",
+	"h3",
+	"h4",
+	"h5",
+	"h6",
+	"header",
+	"hr",
+	"li",
+	"nav",
+	"ol",
+	"p",
+	"pre",
+	"section",
+	"summary",
+	"table",
+	"tbody",
+	"td",
+	"tfoot",
+	"th",
+	"thead",
+	"tr",
+	"ul",
+];
+
+/// Elements whose content is kept byte for byte by `compact`.
+const VERBATIM_TAGS: &[&str] = &["code", "pre", "script", "style", "textarea"];
+
+/// Minify HTML by removing the whitespace between two tags where either is a
+/// block tag, e.g. the newline `push_html` writes after `</p>`. Whitespace in
+/// text, between inline tags, and inside verbatim elements such as `<pre>` and
+/// `<code>` is kept.
+pub fn compact(html: &str) -> String {
+	let mut output = String::with_capacity(html.len());
+	let mut rest = html;
+	/* Whether the last tag, if any, is a block tag. */
+	let mut block = true;
+
+	while let Some(start) = rest.find('<') {
+		let (text, tag) = rest.split_at(start);
+		let tag = &tag[..tag_length(tag)];
+		let name = tag_name(tag);
+
+		if !(text.trim_ascii().is_empty() && (block || BLOCK_TAGS.contains(&name.as_str()))) {
+			output.push_str(text);
+		}
+
+		rest = &rest[start..];
+
+		let length = match VERBATIM_TAGS.contains(&name.as_str()) && !tag.starts_with("</") {
+			true => verbatim_length(rest, &name),
+			false => tag.len(),
+		};
+
+		output.push_str(&rest[..length]);
+		rest = &rest[length..];
+		block = BLOCK_TAGS.contains(&name.as_str());
+	}
+
+	if !(rest.trim_ascii().is_empty() && block) {
+		output.push_str(rest);
+	}
+
+	output
+}
+
+/// The length of the tag or comment at the start of `html`, which starts with
+/// `<`, or of all of `html` if it isn't closed.
+fn tag_length(html: &str) -> usize {
+	if html.starts_with("<!--") {
+		return html.find("-->").map_or(html.len(), |end| end + 3);
+	}
+
+	let mut quote = None;
+
+	for (index, c) in html.char_indices() {
+		match (quote, c) {
+			(None, '"' | '\'') => quote = Some(c),
+			(Some(open), _) if open == c => quote = None,
+			(None, '>') => return index + 1,
+			_ => {}
+		}
+	}
+
+	html.len()
+}
+
+/// The lowercase name of a start or end tag, which is empty for e.g. a comment.
+fn tag_name(tag: &str) -> String {
+	tag.trim_start_matches('<')
+		.trim_start_matches('/')
+		.chars()
+		.take_while(char::is_ascii_alphanumeric)
+		.collect::<String>()
+		.to_ascii_lowercase()
+}
+
+/// The length of the `name` element at the start of `html` through its end tag,
+/// or of all of `html` if it isn't closed.
+fn verbatim_length(html: &str, name: &str) -> usize {
+	let close = format!("</{name}");
+
+	html.to_ascii_lowercase()
+		.find(&close)
+		.map_or(html.len(), |start| start + tag_length(&html[start..]))
+}
@@ -1,8 +1,27 @@
-use crate::error::Fatal;
-use crate::options::Callbacks;
-use ::pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
-use pyo3::prelude::*;
-use std::mem::take;
+use crate::allowlist::Allowlist;
+use crate::autolink::autolink;
+use crate::emoji::emojify;
+use crate::error::{CannotRenderMathError, Fatal};
+use crate::fence::split_info;
+use crate::footnotes::Footnotes;
+use crate::html::{StartTag, ends_line, strip_comments};
+use crate::katex;
+use crate::options::{Callbacks, PyOptions, Settings, kwargs};
+use crate::quotes::{localize, quotes};
+use crate::resolver::{Deferred, Resolver};
+use crate::slug::Slugger;
+use crate::timeout::call;
+use crate::url::{host, is_absolute, is_unsafe, join, md_to_html};
+use ::pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, OffsetIter, Parser, Tag, TagEnd};
+use pulldown_cmark_escape::escape_html;
+use pyo3::{
+	exceptions::PyValueError,
+	prelude::*,
+	types::{IntoPyDict, PyList},
+};
+use std::collections::VecDeque;
+use std::mem::{replace, take};
+use std::ops::Range;
 
 #[derive(Default)]
 enum State {
@@ -10,70 +29,1119 @@ enum State {
 	Default,
 	CodeBlock {
 		buffer: String,
-		language: String,
+		language: Option<String>,
+		attrs: Vec<(String, String)>,
+		/// The source range of the whole block.
+		range: Range<usize>,
+		/// The line of the first line of code in the source, from 1.
+		line: usize,
+		/// The whole info string of a fenced block.
+		info: Option<String>,
 	},
 }
 
-/// Wrapper which extends `pulldown_cmark::Parser` with callbacks.
+/// The table being checked by `strict_tables`.
+#[derive(Default)]
+struct Table {
+	columns: usize,
+	/// The number of the current body row, counting from 1.
+	row: usize,
+	/// The number of cells of the row in the source so far.
+	cells: usize,
+	/// The end of the row in the source, where the parser pads short rows
+	/// with empty cells.
+	row_end: usize,
+	/// The end of the last cell, after which the parser drops cells past the
+	/// header's.
+	cell_end: usize,
+}
+
+/// Wrapper which extends `pulldown_cmark::Parser` with callbacks and settings.
 pub struct EventIter<'p, 'c> {
 	state: State,
-	parser: Parser<'p>,
+	buffer: &'p str,
+	parser: OffsetIter<'p, Resolver<'c>>,
+	/// The source range of the last event read from the parser.
+	range: Range<usize>,
+	/// Errors raised by the `broken_link` callback while the parser runs.
+	deferred: Deferred,
 	callbacks: &'c Callbacks,
+	settings: &'c Settings,
+	/// The `context` item of the input, for callbacks which accept it.
+	context: Option<&'c PyObject>,
+	/// Events already processed, to be yielded before the parser is polled.
+	pending: VecDeque<Event<'p>>,
+	slugger: Slugger,
+	/// For each open link, whether its start was rendered as raw HTML, so its
+	/// end must be too.
+	links: Vec<bool>,
+	footnotes: Footnotes<'p>,
+	/// Whether the last raw HTML ended inside a comment, for `strip_comments`.
+	in_comment: bool,
+	/// Whether a code block is open, and the character before the next text,
+	/// for `smart_quotes_locale`.
+	code_block: bool,
+	previous: char,
+	/// An event read past the end of a run of text, to be filtered next, and
+	/// its source range.
+	lookahead: Option<(Event<'p>, Range<usize>)>,
+	/// The number of open tags, for `max_nesting_depth`.
+	depth: usize,
+	/// Events split from a text by `autolink`, to be yielded before the
+	/// parser is read again.
+	queued: VecDeque<Event<'p>>,
+	/// The number of open tags whose text isn't autolinked, e.g. links.
+	unlinked: usize,
+	/// Whether a code or metadata block is open, whose text isn't emojified.
+	verbatim: bool,
+	/// Whether the open code block started as raw HTML for `code_block_class`,
+	/// so it ends as raw HTML too.
+	classed_code_block: bool,
+	table: Table,
+	/// The number of top-level blocks read, for `max_blocks`.
+	blocks: usize,
+	/// Whether the HTML so far ends a line, for raw HTML standing in for a
+	/// block element.
+	newline: bool,
+	allowlist: Allowlist<'c>,
+	/// A byte offset into the source and its line, from which the line of the
+	/// next code block is counted.
+	line: (usize, usize),
+	batch: Batch<'p>,
+}
+
+/// The stand-ins for the output of batched `code` and `math` calls in the
+/// first pass over a document, told apart from other HTML by their address.
+static CODE_PLACEHOLDER: &str = "<!-- code -->\n";
+static MATH_PLACEHOLDER: &str = "<!-- math -->";
+
+/// The calls to the `code` and `math` callbacks collected in the first pass
+/// over a document for `batch_callbacks`, to be made at once.
+#[derive(Default)]
+struct Batch<'p> {
+	code: Vec<(String, Option<String>)>,
+	math: Vec<(CowStr<'p>, bool)>,
+	/// The events of the document and their source ranges, once the first
+	/// pass is done and the output of the calls is spliced in.
+	events: Option<VecDeque<(Event<'p>, Range<usize>)>>,
 }
 
 impl<'p, 'c> EventIter<'p, 'c> {
-	pub fn new(parser: Parser<'p>, callbacks: &'c Callbacks) -> Self {
+	pub fn new(buffer: &'p str, options: &'c PyOptions, context: Option<&'c PyObject>) -> Self {
+		let (resolver, deferred) =
+			Resolver::new(&options.callbacks, context, options.settings.callback_timeout);
+		let parser = Parser::new_with_broken_link_callback(buffer, options.flags, resolver).into_offset_iter();
+
 		Self {
+			buffer,
 			parser,
+			range: 0..0,
+			deferred,
 			state: State::default(),
-			callbacks,
+			callbacks: &options.callbacks,
+			settings: &options.settings,
+			context,
+			pending: VecDeque::new(),
+			slugger: Slugger::new(options.settings.slug_style.as_deref()),
+			links: Vec::new(),
+			footnotes: Footnotes::new(&options.settings),
+			in_comment: false,
+			code_block: false,
+			previous: ' ',
+			lookahead: None,
+			depth: 0,
+			queued: VecDeque::new(),
+			unlinked: 0,
+			verbatim: false,
+			classed_code_block: false,
+			table: Table::default(),
+			blocks: 0,
+			newline: true,
+			allowlist: Allowlist::new(
+				options.settings.allowed_html_tags.as_deref(),
+				options.settings.disallowed_html.as_deref() == Some("escape"),
+			),
+			line: (0, 1),
+			batch: Batch::default(),
+		}
+	}
+
+	fn math(&mut self, math: CowStr<'p>, display: bool) -> Result<Vec<Event<'p>>, Fatal> {
+		if self.settings.batch_callbacks && self.callbacks.math.is_some() {
+			self.batch.math.push((math, display));
+			return Ok(vec![Event::Html(CowStr::Borrowed(MATH_PLACEHOLDER))]);
 		}
+
+		let buffer = math.as_ref();
+
+		let events = Python::with_gil(|py| -> Result<_, Fatal> {
+			let result = match &self.callbacks.math {
+				Some(callback) => {
+					let kwargs = kwargs(py, &self.callbacks.math_keywords, self.context)?;
+
+					let timeout = self.settings.callback_timeout;
+
+					call(py, callback, (buffer, display), &kwargs, timeout).map_err(|err| match err
+					{
+						/* The callback raises `CannotRenderMathError` to reject its input. */
+						Fatal::BadCallback(err)
+							if err.is_instance_of::<CannotRenderMathError>(py) =>
+						{
+							Fatal::MathFailed {
+								latex: String::from(buffer),
+								source: err,
+							}
+						}
+						err => err,
+					})
+				}
+
+				/* Math is enabled without a callback only if `katex` is set. */
+				None => katex::render(py, buffer, display).map_err(|err| Fatal::MathFailed {
+					latex: String::from(buffer),
+					source: err,
+				}),
+			};
+
+			let result = result?;
+
+			match result.is_none(py) {
+				true => Ok(None),
+				false => Ok(Some(output(py, result)?)),
+			}
+		})?;
+
+		/* `None` leaves the math for `push_html`, e.g. for a client-side
+		 * renderer. */
+		Ok(events.unwrap_or(vec![match display {
+			true => Event::DisplayMath(math),
+			false => Event::InlineMath(math),
+		}]))
 	}
 
-	fn math(&self, buffer: &str, display: bool) -> Result<Event<'p>, Fatal> {
-		/* `self.callbacks.math.unwrap()` is guaranteed, as this function is called
-		 * only if `self.callbacks.math.is_some()`. */
+	fn code(
+		&mut self,
+		buffer: &str,
+		language: Option<&str>,
+		attrs: Vec<(String, String)>,
+		line: usize,
+		info: Option<String>,
+	) -> Result<Vec<Event<'p>>, Fatal> {
+		if self.settings.batch_callbacks {
+			self.batch.code.push((buffer.to_owned(), language.map(String::from)));
+			return Ok(vec![Event::Html(CowStr::Borrowed(CODE_PLACEHOLDER))]);
+		}
+
+		/* `self.callbacks.code_for(language).unwrap()` is guaranteed, as this
+		 * function is called only if `state == State::CodeBlock`, which in turn
+		 * is reached only if there's a callback for the language. */
+		let (callback, keywords) = self.callbacks.code_for(language).unwrap();
+
 		Python::with_gil(|py| {
-			let result = self.callbacks.math.as_ref().unwrap().call1(py, (buffer, display));
-			Ok(Event::Html(result?.extract::<String>(py)?.into()))
+			let kwargs = kwargs(py, keywords, self.context)?;
+
+			if keywords.contains(&"attrs") {
+				kwargs.set_item("attrs", attrs.into_py_dict(py)?)?;
+			}
+
+			if keywords.contains(&"line") {
+				kwargs.set_item("line", line)?;
+			}
+
+			if keywords.contains(&"info") {
+				kwargs.set_item("info", info)?;
+			}
+
+			let result = call(
+				py,
+				callback,
+				(buffer, language),
+				&kwargs,
+				self.settings.callback_timeout,
+			)?;
+			Ok(output(py, result)?)
 		})
 	}
 
-	fn code(&self, buffer: &str, language: &str) -> Result<Event<'p>, Fatal> {
-		/* `self.callbacks.code.unwrap()` is guaranteed, as this function is called
-		 * only if `state == State::CodeBlock`, which in turn is reached only if
-		 * `self.callbacks.code.is_some()`. */
+	fn tasklist_marker(&self, checked: bool) -> Result<Vec<Event<'p>>, Fatal> {
+		/* `self.callbacks.tasklist_marker.unwrap()` is guaranteed, as this
+		 * function is called only if `self.callbacks.tasklist_marker.is_some()`. */
 		Python::with_gil(|py| {
-			let result = self.callbacks.code.as_ref().unwrap().call1(py, (buffer, language));
-			Ok(Event::Html(result?.extract::<String>(py)?.into()))
+			let kwargs = kwargs(py, &self.callbacks.tasklist_marker_keywords, self.context)?;
+			let callback = self.callbacks.tasklist_marker.as_ref().unwrap();
+			let result = call(py, callback, (checked,), &kwargs, self.settings.callback_timeout)?;
+			Ok(output(py, result)?)
 		})
 	}
 
-}
+	/// Resolve the destination and title of a wikilink to `page` with the
+	/// `wikilink` callback, or `None` to leave the link as is.
+	fn wikilink(&self, page: &str, alias: Option<String>) -> Result<Option<(String, String)>, Fatal> {
+		/* `self.callbacks.wikilink.unwrap()` is guaranteed, as this function is
+		 * called only if `self.callbacks.wikilink.is_some()`. */
+		Python::with_gil(|py| {
+			let kwargs = kwargs(py, &self.callbacks.wikilink_keywords, self.context)?;
 
-impl<'p, 'c> Iterator for EventIter<'p, 'c> {
-	type Item = Result<Event<'p>, Fatal>;
+			if self.callbacks.wikilink_keywords.contains(&"alias") {
+				kwargs.set_item("alias", alias)?;
+			}
 
-	fn next(&mut self) -> Option<Self::Item> {
+			let callback = self.callbacks.wikilink.as_ref().unwrap();
+			let result = call(py, callback, (page,), &kwargs, self.settings.callback_timeout)?;
+
+			if result.is_none(py) {
+				return Ok(None);
+			}
+
+			Ok(Some(match result.extract::<String>(py) {
+				Ok(url) => (url, String::new()),
+				Err(_) => result.extract::<(String, String)>(py)?,
+			}))
+		})
+	}
+
+	/// Read the text of the wikilink just started, e.g. `Display` in
+	/// `[[Page|Display]]`, with its formatting flattened, queueing its events to
+	/// be read again.
+	fn alias(&mut self) -> Result<String, Fatal> {
+		let mut events = Vec::new();
+		let mut alias = String::new();
+
+		/* Links can't nest, so this ends at the wikilink's end. */
+		while let Some(event) = self.filter() {
+			let event = event?;
+			let end = matches!(event, Event::End(TagEnd::Link));
+
+			if let Event::Text(text) | Event::Code(text) = &event {
+				alias.push_str(text);
+			}
+
+			events.push(event);
+
+			if end {
+				break;
+			}
+		}
+
+		for event in events.into_iter().rev() {
+			self.queued.push_front(event);
+		}
+
+		Ok(alias)
+	}
+
+	/// The start of a code block left to `push_html`, rendered as it would be
+	/// but with `code_block_class` on the `<code>` of a fenced block.
+	fn code_block_start(&mut self, kind: CodeBlockKind<'p>) -> Event<'p> {
+		let (Some(class), CodeBlockKind::Fenced(info)) = (&self.settings.code_block_class, &kind) else {
+			return Event::Start(Tag::CodeBlock(kind));
+		};
+
+		/* As `push_html` does, the language is the info string up to a space. */
+		let class = match info.split(' ').next().unwrap_or_default() {
+			"" => class.clone(),
+			language => format!("{class} language-{language}"),
+		};
+
+		let before = if self.newline { "" } else { "\n" };
+		let html = StartTag::new("code").attr("class", &class).open();
+		self.classed_code_block = true;
+		Event::Html(format!("{before}<pre>{html}").into())
+	}
+
+	/// The next event of the document, with the output of its callbacks.
+	fn next_event(&mut self) -> Option<Result<Event<'p>, Fatal>> {
+		let event = match self.pending.pop_front() {
+			Some(event) => Ok(event),
+			None => self.poll_headings()?,
+		};
+
+		if let Some(newline) = event.as_ref().ok().and_then(ends_line) {
+			self.newline = newline;
+		}
+
+		Some(event)
+	}
+
+	/// Read the whole document for `batch_callbacks`, then call each callback
+	/// once with the calls collected, and splice their output into the events
+	/// in place of the calls.
+	fn batch(&mut self) -> Result<VecDeque<(Event<'p>, Range<usize>)>, Fatal> {
+		let mut events = Vec::new();
+
+		while let Some(event) = self.next_event() {
+			events.push((event?, self.range()));
+		}
+
+		let code = take(&mut self.batch.code);
+		let math = take(&mut self.batch.math);
+
+		let (mut code, mut math) = Python::with_gil(|py| -> Result<_, Fatal> {
+			let code = match &self.callbacks.code {
+				Some(callback) if !code.is_empty() => {
+					let results = self.call_batch(
+						py,
+						callback,
+						&self.callbacks.code_keywords,
+						"code",
+						code,
+					)?;
+					results.into_iter()
+						.map(|result| output(py, result))
+						.collect::<PyResult<Vec<_>>>()?
+				}
+				_ => Vec::new(),
+			};
+
+			let math = match &self.callbacks.math {
+				Some(callback) if !math.is_empty() => {
+					let items: Vec<(String, bool)> = math
+						.iter()
+						.map(|(math, display)| (math.to_string(), *display))
+						.collect();
+					let results = self.call_batch(
+						py,
+						callback,
+						&self.callbacks.math_keywords,
+						"math",
+						items,
+					)?;
+
+					/* `None` leaves the math for `push_html`, as for single calls. */
+					results.into_iter()
+						.zip(math)
+						.map(|(result, (math, display))| match result.is_none(py) {
+							true => Ok(vec![match display {
+								true => Event::DisplayMath(math),
+								false => Event::InlineMath(math),
+							}]),
+							false => output(py, result),
+						})
+						.collect::<PyResult<Vec<_>>>()?
+				}
+				_ => Vec::new(),
+			};
+
+			Ok((code.into_iter(), math.into_iter()))
+		})?;
+
+		let mut spliced = VecDeque::with_capacity(events.len());
+
+		for (event, range) in events {
+			let output = match &event {
+				Event::Html(CowStr::Borrowed(html)) if html.as_ptr() == CODE_PLACEHOLDER.as_ptr() => {
+					code.next()
+				}
+				Event::Html(CowStr::Borrowed(html)) if html.as_ptr() == MATH_PLACEHOLDER.as_ptr() => {
+					math.next()
+				}
+				_ => None,
+			};
+
+			match output {
+				Some(output) => spliced.extend(output.into_iter().map(|event| (event, range.clone()))),
+				None => spliced.push_back((event, range)),
+			}
+		}
+
+		Ok(spliced)
+	}
+
+	/// Call a batched callback with `items`, checking that it returns a list
+	/// with a result for each.
+	fn call_batch<'py>(
+		&self,
+		py: Python<'py>,
+		callback: &PyObject,
+		keywords: &[&'static str],
+		name: &str,
+		items: Vec<impl IntoPyObject<'py>>,
+	) -> Result<Vec<PyObject>, Fatal> {
+		let count = items.len();
+		let kwargs = kwargs(py, keywords, self.context)?;
+		let result = call(py, callback, (items,), &kwargs, self.settings.callback_timeout)?;
+		let results: Vec<PyObject> = result.extract(py)?;
+
+		if results.len() != count {
+			return Err(PyValueError::new_err(format!(
+				"the batched `{name}` callback returned {} results for {count} calls",
+				results.len()
+			))
+			.into());
+		}
+
+		Ok(results)
+	}
+
+	/// Whether math goes to the `math` callback or KaTeX, rather than being
+	/// rendered as is.
+	fn renders_math(&self) -> bool {
+		self.callbacks.math.is_some() || self.settings.katex
+	}
+
+	/// The source range of the last event read from the parser, which for a
+	/// block is the range of the whole block.
+	pub fn range(&self) -> Range<usize> {
+		self.range.clone()
+	}
+
+	/// Buffer a heading's events until its end, then give its start tag the
+	/// heading's anchor as an ID.
+	fn heading(&mut self, tag: Tag<'p>) -> Result<Event<'p>, Fatal> {
+		let Tag::Heading {
+			level,
+			id,
+			classes,
+			attrs,
+		} = tag
+		else {
+			unreachable!("`EventIter::heading` is only called with `Tag::Heading`");
+		};
+
+		let mut text = String::new();
+
+		while let Some(event) = self.poll() {
+			let event = event?;
+
+			if let Event::Text(content) | Event::Code(content) = &event {
+				text.push_str(content);
+			}
+
+			let end = matches!(event, Event::End(TagEnd::Heading(_)));
+			self.pending.push_back(event);
+
+			if end {
+				break;
+			}
+		}
+
+		let id = self.slugger.anchor(id.as_deref(), &text);
+
+		Ok(Event::Start(Tag::Heading {
+			level,
+			id: Some(id.into()),
+			classes,
+			attrs,
+		}))
+	}
+
+	/// Rewrite a link or image destination per `sanitize`, `base_url`, and
+	/// `rewrite_md_links`.
+	fn destination(&self, url: CowStr<'p>, link: bool) -> CowStr<'p> {
+		if self.settings.sanitize && is_unsafe(&url) {
+			return CowStr::Borrowed("");
+		}
+
+		if is_absolute(&url) {
+			return url;
+		}
+
+		let url = match link && self.settings.rewrite_md_links {
+			true => md_to_html(&url).into(),
+			false => url,
+		};
+
+		match &self.settings.base_url {
+			Some(base) => join(base, &url).into(),
+			None => url,
+		}
+	}
+
+	/// Render an image as `push_html` does, but with `loading="lazy"`, taking
+	/// its alt text from the events up to its end.
+	fn image(&mut self, url: &str, title: &str) -> Result<Event<'p>, Fatal> {
+		let mut alt = String::new();
+		let mut nesting = 0;
+
+		/* The alt text is read from the filtered events, as `push_html` reads
+		 * it from raw events, so a nested image is its text rather than the
+		 * markup it's rewritten to. */
+		while let Some(event) = self.filter() {
+			match self.localize(event?) {
+				Event::Start(_) => nesting += 1,
+				Event::End(_) if nesting == 0 => break,
+				Event::End(_) => nesting -= 1,
+
+				Event::Text(text) | Event::Code(text) | Event::InlineHtml(text) => alt.push_str(&text),
+				Event::InlineMath(text) => alt.push_str(&format!("${text}$")),
+				Event::DisplayMath(text) => alt.push_str(&format!("$${text}$$")),
+				Event::SoftBreak | Event::HardBreak | Event::Rule => alt.push(' '),
+				Event::TaskListMarker(checked) => alt.push_str(if checked { "[x]" } else { "[ ]" }),
+				_ => {}
+			}
+		}
+
+		let html = StartTag::new("img")
+			.url("src", url)
+			.attr("alt", &alt)
+			.attr_if("title", title)
+			.attr("loading", "lazy");
+		Ok(Event::InlineHtml(html.void().into()))
+	}
+
+	/// Prefix a footnote label, which `push_html` uses as its ID, with
+	/// `footnote_prefix`.
+	fn footnote_label(&self, label: CowStr<'p>) -> CowStr<'p> {
+		match &self.settings.footnote_prefix {
+			Some(prefix) => format!("{prefix}{label}").into(),
+			None => label,
+		}
+	}
+
+	/// The line of a byte offset into the source, from 1, counting on from the
+	/// last offset asked for, as code blocks come in source order.
+	fn line_at(&mut self, offset: usize) -> usize {
+		let (start, line) = match self.line {
+			(start, _) if start > offset => (0, 1),
+			line => line,
+		};
+
+		self.line = (offset, line + self.buffer[start..offset].matches('\n').count());
+		self.line.1
+	}
+
+	/// Read a paragraph for `figure_images`, and if it's an image with a title
+	/// and nothing else, render it as a figure captioned with the title. The
+	/// events are queued to be read again, so the image is rendered as usual,
+	/// and the start of the figure is returned, or `None` for a paragraph.
+	fn figure(&mut self) -> Result<Option<String>, Fatal> {
+		let mut events = Vec::new();
+
+		/* Paragraphs can't nest, so this ends at the paragraph's end. */
+		while let Some(event) = self.filter() {
+			match event? {
+				Event::End(TagEnd::Paragraph) => break,
+				event => events.push(event),
+			}
+		}
+
+		/* The image is alone if it ends with the paragraph, as alt text may
+		 * hold images of its own. */
+		let mut depth = 0;
+		let alone = events.iter().position(|event| {
+			match event {
+				Event::Start(_) => depth += 1,
+				Event::End(_) => depth -= 1,
+				_ => {}
+			}
+
+			depth == 0
+		}) == Some(events.len().saturating_sub(1));
+
+		let caption = match events.first_mut() {
+			Some(Event::Start(Tag::Image { title, .. })) if alone && !title.is_empty() => {
+				replace(title, CowStr::Borrowed(""))
+			}
+			_ => {
+				events.push(Event::End(TagEnd::Paragraph));
+
+				for event in events.into_iter().rev() {
+					self.queued.push_front(event);
+				}
+
+				return Ok(None);
+			}
+		};
+
+		let mut html = String::from("<figcaption>");
+		/* Writing to a `String` can't fail. */
+		escape_html(&mut html, &caption).unwrap();
+		html.push_str("</figcaption></figure>\n");
+		events.push(Event::Html(html.into()));
+
+		for event in events.into_iter().rev() {
+			self.queued.push_front(event);
+		}
+
+		let before = if self.newline { "" } else { "\n" };
+		Ok(Some(format!("{before}<figure>")))
+	}
+
+	/// Whether `url` leads off `base_host`, or the host of `base_url` if there's
+	/// no `base_host`. Without either, every URL with a host is external.
+	fn is_external(&self, url: &str) -> bool {
+		let base = match &self.settings.base_host {
+			Some(base) => Some(base.to_ascii_lowercase()),
+			None => self.settings.base_url.as_deref().and_then(host),
+		};
+
+		host(url).is_some_and(|host| Some(host) != base)
+	}
+
+	/// Poll the filtered events, and rewrite them per the settings.
+	fn poll(&mut self) -> Option<Result<Event<'p>, Fatal>> {
 		loop {
-			let event = match self.parser.next() {
+			let event = match self.filter() {
+				Some(Ok(event)) => event,
+				Some(err) => return Some(err),
+				None => {
+					/* The section is taken, so it's only emitted once. */
+					self.pending.extend(self.footnotes.section());
+					return self.pending.pop_front().map(Ok);
+				}
+			};
+
+			/* Wikilinks are resolved first, so they're rewritten like any other link. */
+			let event = match event {
+				Event::Start(Tag::Link {
+					link_type: link_type @ LinkType::WikiLink { has_pothole },
+					dest_url,
+					title,
+					id,
+				}) if self.callbacks.wikilink.is_some() => {
+					let alias = match has_pothole
+						&& self.callbacks.wikilink_keywords.contains(&"alias")
+					{
+						true => match self.alias() {
+							Ok(alias) => Some(alias),
+							Err(err) => return Some(Err(err)),
+						},
+						false => None,
+					};
+
+					let (dest_url, title) = match self.wikilink(&dest_url, alias) {
+						Ok(Some((url, title))) => (url.into(), title.into()),
+						Ok(None) => (dest_url, title),
+						Err(err) => return Some(Err(err)),
+					};
+
+					Event::Start(Tag::Link {
+						link_type,
+						dest_url,
+						title,
+						id,
+					})
+				}
+
+				event => event,
+			};
+
+			let event = match event {
+				Event::Start(Tag::Link {
+					link_type,
+					dest_url,
+					title,
+					id,
+				}) => {
+					/* Email autolinks have no scheme until rendered, so they are skipped. */
+					let dest_url = match link_type {
+						LinkType::Email => dest_url,
+						_ => self.destination(dest_url, true),
+					};
+
+					let external = self.settings.nofollow_external && self.is_external(&dest_url);
+					self.links.push(external);
+
+					match external {
+						true => Event::InlineHtml(
+							StartTag::new("a")
+								.url("href", &dest_url)
+								.attr_if("title", &title)
+								.attr("rel", "nofollow noopener")
+								.attr("target", "_blank")
+								.open()
+								.into(),
+						),
+						false => Event::Start(Tag::Link {
+							link_type,
+							dest_url,
+							title,
+							id,
+						}),
+					}
+				}
+
+				Event::End(TagEnd::Link) => match self.links.pop() {
+					Some(true) if self.settings.accessible => Event::InlineHtml(
+						"<span class=\"sr-only\"> (opens in a new tab)</span></a>".into(),
+					),
+					Some(true) => Event::InlineHtml("</a>".into()),
+					_ => Event::End(TagEnd::Link),
+				},
+
+				Event::Start(Tag::Heading {
+					level,
+					id,
+					classes,
+					attrs,
+				}) => Event::Start(Tag::Heading {
+					level: shift(level, self.settings.heading_offset),
+					id,
+					classes,
+					attrs,
+				}),
+
+				Event::End(TagEnd::Heading(level)) => {
+					Event::End(TagEnd::Heading(shift(level, self.settings.heading_offset)))
+				}
+
+				Event::SoftBreak if self.settings.hardbreaks => Event::HardBreak,
+
+				Event::Start(Tag::Paragraph) if self.settings.figure_images => match self.figure() {
+					Ok(Some(html)) => Event::Html(html.into()),
+					Ok(None) => Event::Start(Tag::Paragraph),
+					Err(err) => return Some(Err(err)),
+				},
+
+				Event::FootnoteReference(label) if self.settings.collect_footnotes => {
+					self.footnotes.reference(&label)
+				}
+
+				Event::Start(Tag::FootnoteDefinition(label)) if self.settings.collect_footnotes => {
+					let mut events = Vec::new();
+
+					/* Definitions can't nest, so this ends at the definition's end. */
+					loop {
+						match self.poll()? {
+							Ok(Event::End(TagEnd::FootnoteDefinition)) => break,
+							Ok(event) => events.push(event),
+							err => return Some(err),
+						}
+					}
+
+					self.footnotes.define(label.into_string(), events);
+					continue;
+				}
+
+				Event::FootnoteReference(label) if self.settings.accessible => {
+					let label = self.footnote_label(label);
+					self.footnotes.inline_reference(&label)
+				}
+
+				Event::Start(Tag::FootnoteDefinition(label)) if self.settings.accessible => {
+					let label = self.footnote_label(label);
+					let before = if self.newline { "" } else { "\n" };
+					Event::Html(
+						format!("{before}{}", self.footnotes.inline_definition(&label)).into(),
+					)
+				}
+
+				/* The end goes as raw HTML with its start, as `push_html` would write
+				 * it, but a collected definition ends at the end event itself. */
+				Event::End(TagEnd::FootnoteDefinition)
+					if self.settings.accessible && !self.settings.collect_footnotes =>
+				{
+					Event::Html("</div>\n".into())
+				}
+
+				Event::FootnoteReference(label) => Event::FootnoteReference(self.footnote_label(label)),
+
+				Event::Start(Tag::FootnoteDefinition(label)) => {
+					Event::Start(Tag::FootnoteDefinition(self.footnote_label(label)))
+				}
+
+				Event::Start(Tag::Image {
+					link_type,
+					dest_url,
+					title,
+					id,
+				}) => {
+					let dest_url = self.destination(dest_url, false);
+
+					match self.settings.lazy_images {
+						true => match self.image(&dest_url, &title) {
+							Ok(event) => event,
+							Err(err) => return Some(Err(err)),
+						},
+						false => Event::Start(Tag::Image {
+							link_type,
+							dest_url,
+							title,
+							id,
+						}),
+					}
+				}
+
+				Event::Start(Tag::BlockQuote(Some(kind)))
+					if self.settings.admonition_style.is_some() =>
+				{
+					/* `admonition_style.is_some()` guarantees the admonition. */
+					let html = self.settings.admonition(kind).unwrap();
+					let before = if self.newline { "" } else { "\n" };
+					Event::Html(format!("{before}{html}").into())
+				}
+
+				Event::End(TagEnd::BlockQuote(Some(_))) if self.settings.admonition_style.is_some() => {
+					Event::Html("</div>\n".into())
+				}
+
+				Event::Start(
+					tag @ (Tag::DefinitionList
+					| Tag::DefinitionListTitle
+					| Tag::DefinitionListDefinition),
+				) => {
+					match self.settings.definition_class(tag.to_end()) {
+						Some((name, class)) => {
+							/* As `push_html` does, the list is on lines of its own, and
+							 * its items start on new lines. */
+							let before = if self.newline { "" } else { "\n" };
+							let after = if matches!(tag, Tag::DefinitionList) {
+								"\n"
+							} else {
+								""
+							};
+							let html = StartTag::new(name).attr("class", class).open();
+
+							Event::Html(format!("{before}{html}{after}").into())
+						}
+
+						None => Event::Start(tag),
+					}
+				}
+
+				Event::End(
+					end @ (TagEnd::DefinitionList
+					| TagEnd::DefinitionListTitle
+					| TagEnd::DefinitionListDefinition),
+				) => match self.settings.definition_class(end) {
+					Some((name, _)) => Event::Html(format!("</{name}>\n").into()),
+					None => Event::End(end),
+				},
+
+				event => event,
+			};
+
+			return Some(Ok(self.localize(event)));
+		}
+	}
+
+	/// Count the cells of each table body row in the source, for
+	/// `strict_tables`, failing at the end of a row whose count differs from
+	/// the header's.
+	fn check_table(&mut self, event: &Event) -> Result<(), Fatal> {
+		let table = &mut self.table;
+
+		match event {
+			Event::Start(Tag::Table(alignments)) => {
+				*table = Table {
+					columns: alignments.len(),
+					..Table::default()
+				};
+			}
+
+			Event::Start(Tag::TableRow) => {
+				table.row += 1;
+				table.cells = 0;
+				table.row_end = self.range.end;
+			}
+
+			/* Padding is empty, at the row's end. */
+			Event::Start(Tag::TableCell)
+				if table.row > 0 && !(self.range.is_empty() && self.range.start == table.row_end) =>
+			{
+				table.cells += 1;
+			}
+
+			Event::End(TagEnd::TableCell) => table.cell_end = self.range.end,
+
+			Event::End(TagEnd::TableRow) => {
+				/* What's left after the last cell is its closing pipe, if any,
+				 * then each dropped cell. */
+				let rest = self.buffer[table.cell_end..table.row_end].trim();
+				let rest = rest.strip_prefix('|').unwrap_or(rest);
+				let rest = rest.strip_suffix('|').unwrap_or(rest);
+
+				if !rest.trim().is_empty() {
+					table.cells += rest.replace("\\|", "").split('|').count();
+				}
+
+				if table.cells != table.columns {
+					return Err(Fatal::BadTable {
+						row: table.row,
+						cells: table.cells,
+						columns: table.columns,
+					});
+				}
+			}
+
+			_ => {}
+		}
+
+		Ok(())
+	}
+
+	/// Poll the next event, giving headings their IDs and anchor links.
+	fn poll_headings(&mut self) -> Option<Result<Event<'p>, Fatal>> {
+		let event = match self.poll()? {
+			Ok(Event::Start(tag @ Tag::Heading { .. })) if self.settings.auto_heading_ids => {
+				self.heading(tag)
+			}
+			event => event,
+		};
+
+		/* The anchor goes before the heading's text, which may be pending. */
+		if let (true, Ok(Event::Start(Tag::Heading { id: Some(id), .. }))) =
+			(self.settings.anchor_links, &event)
+		{
+			let anchor = self.settings.anchor(id);
+			self.pending.push_front(Event::InlineHtml(anchor.into()));
+		}
+
+		Some(event)
+	}
+
+	/// Replace the quotes in text with those of `smart_quotes_locale`, keeping
+	/// track of the character before each text across inline markup.
+	fn localize(&mut self, event: Event<'p>) -> Event<'p> {
+		let Some(quotes) = self.settings.smart_quotes_locale.as_deref().and_then(quotes) else {
+			return event;
+		};
+
+		match &event {
+			Event::Text(text) if !self.code_block => {
+				return Event::Text(localize(text, &quotes, &mut self.previous).into());
+			}
+
+			Event::Start(Tag::CodeBlock(_)) => self.code_block = true,
+			Event::End(TagEnd::CodeBlock) => self.code_block = false,
+
+			Event::Code(code) => self.previous = code.chars().last().unwrap_or(self.previous),
+
+			/* Inline markup doesn't separate a quote from the text before it. */
+			Event::Start(
+				Tag::Emphasis
+				| Tag::Strong
+				| Tag::Strikethrough
+				| Tag::Superscript
+				| Tag::Subscript
+				| Tag::Link { .. },
+			)
+			| Event::End(
+				TagEnd::Emphasis
+				| TagEnd::Strong
+				| TagEnd::Strikethrough
+				| TagEnd::Superscript
+				| TagEnd::Subscript
+				| TagEnd::Link,
+			)
+			| Event::InlineHtml(_) => {}
+
+			_ => self.previous = ' ',
+		}
+
+		event
+	}
+
+	/// Poll the parser, and feed math, code, and task list markers to their
+	/// callbacks.
+	fn filter(&mut self) -> Option<Result<Event<'p>, Fatal>> {
+		loop {
+			if let Some(event) = self.queued.pop_front() {
+				return Some(Ok(event));
+			}
+
+			/* Blocks are only cut between each other, so no tag is left open. */
+			if self.depth == 0 && self.settings.max_blocks.is_some_and(|limit| self.blocks >= limit) {
+				return None;
+			}
+
+			let event = self
+				.lookahead
+				.take()
+				.or_else(|| self.parser.next())
+				.map(|(event, range)| {
+					self.range = range;
+					event
+				});
+
+			if let Some(err) = self.deferred.take() {
+				return Some(Err(err));
+			}
+
+			let event = match event {
 				Some(event) => event,
 				None => {
 					/* If we're in a codeblock, flush the buffer before we close the iterator. */
-					if let State::CodeBlock { buffer, language } = take(&mut self.state) {
-						return Some(self.code(&buffer, &language));
+					if let State::CodeBlock {
+						buffer,
+						language,
+						attrs,
+						line,
+						info,
+						..
+					} = take(&mut self.state)
+					{
+						match self.code(&buffer, language.as_deref(), attrs, line, info) {
+							Ok(events) => self.queued.extend(events),
+							Err(err) => return Some(Err(err)),
+						}
+
+						continue;
 					} else {
 						return None;
 					}
 				}
 			};
 
-			if let State::CodeBlock { buffer, language } = &mut self.state {
+			match event {
+				Event::Start(_) => self.depth += 1,
+				Event::End(_) => self.depth -= 1,
+				_ => {}
+			}
+
+			match event {
+				Event::Start(
+					Tag::Link { .. }
+					| Tag::Image { .. }
+					| Tag::CodeBlock(_)
+					| Tag::MetadataBlock(_),
+				) => {
+					self.unlinked += 1;
+				}
+
+				Event::End(
+					TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock | TagEnd::MetadataBlock(_),
+				) => {
+					self.unlinked -= 1;
+				}
+
+				_ => {}
+			}
+
+			match event {
+				Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => self.verbatim = true,
+				Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => self.verbatim = false,
+				_ => {}
+			}
+
+			/* A block ends at its end tag, or at once if it has none, e.g. a rule. */
+			if self.depth == 0 && !matches!(event, Event::Start(_)) {
+				self.blocks += 1;
+			}
+
+			if let Some(limit) = self.settings.max_nesting_depth.filter(|&limit| self.depth > limit) {
+				return Some(Err(Fatal::TooDeep { limit }));
+			}
+
+			if self.settings.strict_tables {
+				if let Err(err) = self.check_table(&event) {
+					return Some(Err(err));
+				}
+			}
+
+			if let State::CodeBlock {
+				buffer,
+				language,
+				attrs,
+				range,
+				line,
+				info,
+			} = &mut self.state
+			{
 				match event {
 					Event::End(TagEnd::CodeBlock) => {
-						let (buffer, language) = (take(buffer), take(language));
+						let (buffer, language, attrs, line, info) =
+							(take(buffer), take(language), take(attrs), *line, take(info));
+						/* The callback's output stands for the whole block. */
+						self.range = take(range);
 						self.state = State::Default;
-						return Some(self.code(&buffer, &language));
+
+						match self.code(&buffer, language.as_deref(), attrs, line, info) {
+							Ok(events) => self.queued.extend(events),
+							Err(err) => return Some(Err(err)),
+						}
+
+						continue;
 					}
 
 					Event::Text(text) => {
@@ -86,27 +1154,215 @@ impl<'p, 'c> Iterator for EventIter<'p, 'c> {
 			}
 
 			match event {
-				Event::InlineMath(math) if self.callbacks.math.is_some() => {
-					return Some(self.math(math.as_ref(), false));
+				Event::Html(html) | Event::InlineHtml(html) if self.settings.escape_html => {
+					return Some(Ok(Event::Text(html)));
+				}
+
+				/* Only raw HTML from the source is dropped, not callback output. */
+				Event::Html(_) | Event::InlineHtml(_) if self.settings.sanitize => continue,
+
+				Event::Html(html) if self.settings.allowed_html_tags.is_some() => {
+					/* As with `strip_comments`, chunks left blank are dropped. */
+					match self.allowlist.filter(&html) {
+						html if html.trim().is_empty() => continue,
+						html => return Some(Ok(Event::Html(html.into()))),
+					}
+				}
+
+				/* Inline HTML is whole tags, but for a stray `<` in a tag. */
+				Event::InlineHtml(html) if self.settings.allowed_html_tags.is_some() => {
+					let html = self.allowlist.filter(&html) + &self.allowlist.finish();
+
+					match html.is_empty() {
+						true => continue,
+						false => return Some(Ok(Event::InlineHtml(html.into()))),
+					}
+				}
+
+				/* A tag left open at the end of an HTML block is malformed. */
+				Event::End(TagEnd::HtmlBlock) if self.settings.allowed_html_tags.is_some() => {
+					let html = self.allowlist.finish();
+					self.queued.push_back(Event::End(TagEnd::HtmlBlock));
+
+					if !html.is_empty() {
+						return Some(Ok(Event::Html(html.into())));
+					}
+				}
+
+				/* Chunks left blank by stripping, e.g. the line of a comment in an
+				 * HTML block, are dropped. */
+				Event::Html(html) if self.settings.strip_comments => {
+					match strip_comments(&html, &mut self.in_comment) {
+						html if html.trim().is_empty() => continue,
+						html => return Some(Ok(Event::Html(html.into_owned().into()))),
+					}
+				}
+
+				Event::InlineHtml(html) if self.settings.strip_comments => {
+					match strip_comments(&html, &mut self.in_comment) {
+						html if html.trim().is_empty() => continue,
+						html => return Some(Ok(Event::InlineHtml(html.into_owned().into()))),
+					}
+				}
+
+				Event::InlineMath(math) if self.renders_math() => match self.math(math, false) {
+					Ok(events) => self.queued.extend(events),
+					Err(err) => return Some(Err(err)),
+				},
+
+				Event::DisplayMath(math) if self.renders_math() => match self.math(math, true) {
+					Ok(events) => self.queued.extend(events),
+					Err(err) => return Some(Err(err)),
+				},
+
+				Event::TaskListMarker(checked) if self.callbacks.tasklist_marker.is_some() => {
+					match self.tasklist_marker(checked) {
+						Ok(events) => self.queued.extend(events),
+						Err(err) => return Some(Err(err)),
+					}
 				}
 
-				Event::DisplayMath(math) if self.callbacks.math.is_some() => {
-					return Some(self.math(math.as_ref(), true));
+				/* As `push_html` renders it, but labeled with its state. */
+				Event::TaskListMarker(checked) if self.settings.accessible => {
+					let html = match checked {
+						true => {
+							"<input disabled=\"\" type=\"checkbox\" checked=\"\" aria-label=\"Completed task\"/>\n"
+						}
+						false => {
+							"<input disabled=\"\" type=\"checkbox\" aria-label=\"Incomplete task\"/>\n"
+						}
+					};
+
+					return Some(Ok(Event::InlineHtml(html.into())));
 				}
 
-				Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(language)))
-					if self.callbacks.code.is_some() =>
+				/* `smart_punctuation` splits text at quotes, which are localized
+				 * from the characters around them, and the parser splits text at
+				 * characters which may be markup, such as `_` in URLs and
+				 * shortcodes, so runs of text are joined. */
+				Event::Text(text)
+					if self.settings.smart_quotes_locale.is_some()
+						|| self.settings.autolink || self.settings.emoji =>
 				{
+					let mut text = text.into_string();
+
+					loop {
+						match self.parser.next() {
+							Some((Event::Text(more), _)) => text.push_str(&more),
+							event => {
+								self.lookahead = event;
+								break;
+							}
+						}
+					}
+
+					if self.settings.emoji && !self.verbatim {
+						text = emojify(&text, self.settings.emoji_map.as_ref()).into_owned();
+					}
+
+					if self.settings.autolink && self.unlinked == 0 {
+						self.queued.extend(autolink(&text));
+						continue;
+					}
+
+					return Some(Ok(Event::Text(text.into())));
+				}
+
+				Event::Start(Tag::CodeBlock(kind)) if self.callbacks.code.is_some() => {
+					/* Indented code blocks and bare fences carry no language, which
+					 * reaches the callback as `None`. */
+					let (language, attrs) = match &kind {
+						CodeBlockKind::Fenced(info) => split_info(info),
+						CodeBlockKind::Indented => (None, Vec::new()),
+					};
+
+					if self.callbacks.code_for(language).is_none() {
+						return Some(Ok(self.code_block_start(kind)));
+					}
+
+					/* Fenced code starts on the line after its fence. */
+					let info = match &kind {
+						CodeBlockKind::Fenced(info) => Some(info.to_string()),
+						CodeBlockKind::Indented => None,
+					};
+
 					self.state = State::CodeBlock {
 						buffer: String::new(),
-						language: String::from(language),
+						language: language.map(String::from),
+						attrs,
+						range: self.range(),
+						line: self.line_at(self.range.start) + usize::from(info.is_some()),
+						info,
 					};
 
 					continue;
 				}
 
+				Event::Start(Tag::CodeBlock(kind)) => return Some(Ok(self.code_block_start(kind))),
+
+				Event::End(TagEnd::CodeBlock) if self.classed_code_block => {
+					self.classed_code_block = false;
+					return Some(Ok(Event::Html("</code></pre>\n".into())));
+				}
+
 				default => return Some(Ok(default)),
 			};
 		}
 	}
 }
+
+/// Shift a heading level by `offset`, clamped to `h1`-`h6`.
+pub fn shift(level: HeadingLevel, offset: i8) -> HeadingLevel {
+	let level = (level as i8).saturating_add(offset).clamp(1, 6);
+	/* The clamp keeps the level in range. */
+	HeadingLevel::try_from(level as usize).unwrap()
+}
+
+/// Convert the return value of a callback into events: a string is raw HTML, a
+/// `(content, is_html)` tuple is raw HTML or text to escape, and a list is an
+/// event for each of its items, in order.
+pub fn output<'p>(py: Python<'_>, result: PyObject) -> PyResult<Vec<Event<'p>>> {
+	let items = match result.downcast_bound::<PyList>(py) {
+		Ok(items) => items.iter().map(Bound::unbind).collect(),
+		Err(_) => vec![result],
+	};
+
+	items.into_iter()
+		.map(|item| {
+			let (content, is_html) = match item.extract::<String>(py) {
+				Ok(content) => (content, true),
+				Err(_) => item.extract::<(String, bool)>(py)?,
+			};
+
+			Ok(match is_html {
+				true => Event::Html(content.into()),
+				false => Event::Text(content.into()),
+			})
+		})
+		.collect()
+}
+
+impl<'p, 'c> Iterator for EventIter<'p, 'c> {
+	type Item = Result<Event<'p>, Fatal>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if !self.settings.batch_callbacks {
+			return self.next_event();
+		}
+
+		if self.batch.events.is_none() {
+			let events = self.batch();
+			/* After an error, the iterator ends. */
+			self.batch.events = Some(VecDeque::new());
+
+			match events {
+				Ok(events) => self.batch.events = Some(events),
+				Err(err) => return Some(Err(err)),
+			}
+		}
+
+		let (event, range) = self.batch.events.as_mut()?.pop_front()?;
+		self.range = range;
+		Some(Ok(event))
+	}
+}
@@ -0,0 +1,70 @@
+use crate::events::{fields, name, to_object};
+use crate::inputs;
+use crate::options::PyOptions;
+use ::pulldown_cmark::{Event, Parser};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde_json::{Map, Value};
+
+/// Build the syntax tree of a Markdown string, nesting each tag's events
+/// under it as `children`.
+fn tree(markdown: &str, options: &PyOptions) -> Value {
+	let mut document = Map::new();
+	document.insert(String::from("type"), Value::from("document"));
+
+	let mut stack = vec![(document, Vec::new())];
+
+	for event in Parser::new_ext(markdown, options.flags) {
+		match event {
+			Event::Start(tag) => {
+				let mut node = Map::new();
+				node.insert(String::from("type"), Value::from(name(&tag.to_end())));
+				node.extend(fields(tag));
+				stack.push((node, Vec::new()));
+			}
+
+			Event::End(_) => {
+				/* The parser balances tags, so the document is never popped. */
+				let (mut node, children) = stack.pop().unwrap();
+				node.insert(String::from("children"), Value::from(children));
+				stack.last_mut().unwrap().1.push(Value::from(node));
+			}
+
+			event => stack.last_mut().unwrap().1.push(Value::from(to_object(event))),
+		}
+	}
+
+	let (mut document, children) = stack.pop().unwrap();
+	document.insert(String::from("children"), Value::from(children));
+	Value::from(document)
+}
+
+/// Serialize a list of Markdown strings into JSON syntax trees.
+///
+/// Each tree is a `document` node. Tags become nodes whose `type` is the tag
+/// name, with the fields and `children` of the tag, and other events become
+/// leaf nodes; see `parse_events` for their names and fields.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     An iterable of Markdown `str` or UTF-8 `bytes` to serialize.
+/// options
+///     The Markdown extensions to enable. Callbacks are ignored.
+///
+/// Returns
+/// -------
+/// A list of JSON strings which preserves the indices of `markdown`.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None))]
+pub fn to_json(py: Python, markdown: &Bound<'_, PyAny>, options: Option<&PyOptions>) -> PyResult<Vec<String>> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let inputs = inputs(markdown)?;
+
+	Ok(py.allow_threads(move || {
+		inputs.par_iter()
+			.map(|buffer| tree(buffer, options).to_string())
+			.collect()
+	}))
+}
@@ -0,0 +1,16 @@
+use pyo3::{prelude::*, sync::GILOnceCell, types::IntoPyDict};
+
+/// The `katex` package, imported once per interpreter.
+static MODULE: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+
+/// Import the `katex` package, or return it if it's already imported.
+pub fn module(py: Python<'_>) -> PyResult<&Bound<'_, PyModule>> {
+	MODULE.get_or_try_init(py, || Ok(py.import("katex")?.unbind()))
+		.map(|module| module.bind(py))
+}
+
+/// Render LaTeX into HTML with `katex.render`.
+pub fn render(py: Python<'_>, latex: &str, display: bool) -> PyResult<PyObject> {
+	let kwargs = [("display_mode", display)].into_py_dict(py)?;
+	Ok(module(py)?.call_method("render", (latex,), Some(&kwargs))?.unbind())
+}
@@ -1,62 +1,475 @@
+mod allowlist;
+mod autolink;
+mod cache;
+mod cmark;
+mod emoji;
 mod error;
+mod events;
+mod extract;
+mod fence;
+mod files;
+mod footnotes;
+mod frontmatter;
+mod html;
 mod iter;
+mod json;
+mod katex;
+mod math;
 mod options;
+mod quotes;
+mod renderer;
+mod resolver;
+mod slug;
+mod sourcemap;
+mod stream;
+mod text;
+mod timeout;
+mod url;
+mod validate;
 
-use crate::error::{BadCallbackError, Fatal, PulldownCmarkError};
+use crate::cache::{Cache, render_cached};
+use crate::cmark::normalize;
+use crate::error::{
+	BadCallbackError, BadFrontmatterError, BadTableError, CallbackTimeoutError, CannotConfigMathError,
+	CannotRenderMathError, Fatal, PulldownCmarkError,
+};
+use crate::events::parse_events;
+use crate::extract::{extract_links, extract_toc};
+use crate::files::render_files;
+use crate::frontmatter::{parse_frontmatter, split_frontmatter};
+use crate::html::compact;
 use crate::iter::EventIter;
+use crate::json::to_json;
 use crate::options::PyOptions;
-use ::pulldown_cmark::{Parser, html::push_html};
+use crate::renderer::Renderer;
+use crate::sourcemap::render_with_sourcemap;
+use crate::stream::{render_iter, render_stream};
+use crate::text::{stats, to_plaintext};
+use crate::validate::validate_options;
+use ::pulldown_cmark::html::push_html;
 use itertools::process_results;
-use pyo3::{Python, prelude::*, types::PyList, wrap_pyfunction};
-use rayon::prelude::*;
+use pyo3::{
+	IntoPyObjectExt, Python,
+	exceptions::{PyTypeError, PyUnicodeDecodeError, PyValueError},
+	prelude::*,
+	types::{PyBytes, PyDict, PyIterator, PyString},
+	wrap_pyfunction,
+};
+use rayon::{ThreadPoolBuilder, prelude::*};
+use std::collections::HashMap;
 
-/// Render a list of Markdown strings into a list of HTML strings.
+/// Render a single Markdown string into HTML, passing `context` to callbacks
+/// which accept it.
+fn render_one(buffer: &str, options: &PyOptions, context: Option<&PyObject>) -> Result<String, Fatal> {
+	let mut output = String::with_capacity(buffer.len());
+	render_into(&mut output, buffer, options, context)?;
+	Ok(output)
+}
+
+/// Render a single Markdown string into HTML as `render_one` does, appending to
+/// `output`, so its allocation can be reused.
+fn render_into(
+	output: &mut String,
+	buffer: &str,
+	options: &PyOptions,
+	context: Option<&PyObject>,
+) -> Result<(), Fatal> {
+	check_size(buffer, options)?;
+	let buffer = math::normalize(buffer, options.flags, &options.settings.math_delimiters);
+	let iter = EventIter::new(&buffer, options, context);
+	let wrapper = options.settings.wrapper();
+	let start = output.len();
+
+	if let Some((open, _)) = &wrapper {
+		output.push_str(open);
+	}
+
+	process_results(iter, |events| push_html(output, events))?;
+
+	if let Some((_, close)) = &wrapper {
+		output.push_str(close);
+	}
+
+	if options.settings.compact {
+		let html = compact(&output[start..]);
+		output.truncate(start);
+		output.push_str(&html);
+	}
+
+	Ok(())
+}
+
+/// Check that an input is within `max_input_bytes`, before it's parsed.
+fn check_size(buffer: &str, options: &PyOptions) -> Result<(), Fatal> {
+	match options.settings.max_input_bytes {
+		Some(limit) if buffer.len() > limit => Err(Fatal::TooLarge {
+			size: buffer.len(),
+			limit,
+		}),
+		_ => Ok(()),
+	}
+}
+
+/// Check that a `context` list, if any, has one item for each input.
+fn check_context(context: Option<&Vec<PyObject>>, inputs: usize) -> PyResult<()> {
+	match context {
+		Some(context) if context.len() != inputs => Err(PyValueError::new_err(format!(
+			"`context` must have one item per input: got {} for {inputs}",
+			context.len()
+		))),
+		_ => Ok(()),
+	}
+}
+
+/// Extract Markdown strings from any Python iterable, such as a list, tuple, or
+/// generator.
+fn inputs(markdown: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+	markdown_iter(markdown)?
+		.enumerate()
+		.map(|(index, item)| input(index, &item?))
+		.collect()
+}
+
+/// Iterate the items of an iterable of Markdown strings. A bare string or
+/// `bytes` is refused, rather than read as a sequence of characters or bytes.
+fn markdown_iter<'py>(markdown: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyIterator>> {
+	if markdown.is_instance_of::<PyString>() || markdown.is_instance_of::<PyBytes>() {
+		return Err(PyTypeError::new_err("expected an iterable of strings, not a string"));
+	}
+
+	markdown.try_iter()
+}
+
+/// Extract the Markdown string at `index` of its input, which is either a
+/// `str` or UTF-8 `bytes`.
+fn input(index: usize, item: &Bound<'_, PyAny>) -> PyResult<String> {
+	let Ok(bytes) = item.downcast::<PyBytes>() else {
+		return item
+			.extract()
+			.map_err(|_| PyTypeError::new_err(format!("input {index} is not a string")));
+	};
+
+	match std::str::from_utf8(bytes.as_bytes()) {
+		Ok(buffer) => Ok(String::from(buffer)),
+		Err(err) => {
+			let py = item.py();
+			let cause = PyUnicodeDecodeError::new_utf8(py, bytes.as_bytes(), err)?;
+			let wrapped = PyValueError::new_err(format!("input {index} is not valid UTF-8"));
+			wrapped.set_cause(py, Some(PyErr::from_value(cause.into_any())));
+			Err(wrapped)
+		}
+	}
+}
+
+/// Render a Markdown string into an HTML string, or a list of Markdown strings
+/// into a list of HTML strings.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     A Markdown `str` or UTF-8 `bytes` to render, or an iterable of them.
+/// options
+///     The Markdown extensions to enable.
+/// threads
+///     The number of threads with which to render, or `None` to share the
+///     global pool of one thread per core. `1` renders sequentially. A new
+///     pool is created for each call, so render large batches at once rather
+///     than one string per call. Callbacks take the GIL, so they run one at a
+///     time, while parsing runs in parallel.
+/// context
+///     A list with an item for each string of `markdown`, such as its path,
+///     which is passed as the `context` keyword to each callback that accepts
+///     one while that string is rendered. For a single string, the item
+///     itself.
+///
+/// Returns
+/// -------
+/// The HTML string of a single `markdown` string, or, for an iterable, a list
+/// of HTML strings which preserves the indices of `markdown`.
+///
+/// Raises
+/// ------
+/// BadCallbackError
+///    If a user callback fails while Markdown is parsed.
+/// CannotRenderMathError
+///    If the `math` callback raises `CannotRenderMathError` for its input, or
+///    `katex` fails to render it.
+/// BadTableError
+///    If `strict_tables` is set and a table row doesn't have one cell for each
+///    column of its header.
+/// PulldownCmarkError
+///    If `threads` is zero, or its pool can't be created.
+/// TypeError
+///    If `markdown` is neither a string nor an iterable of strings.
+/// ValueError
+///    If `context` doesn't have an item for each string of `markdown`, or an
+///    input exceeds `max_input_bytes` or `max_nesting_depth`.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None, threads = None, context = None))]
+fn render(
+	py: Python,
+	markdown: &Bound<'_, PyAny>,
+	options: Option<&PyOptions>,
+	threads: Option<usize>,
+	context: Option<PyObject>,
+) -> PyResult<PyObject> {
+	match render_html(py, markdown, options, threads, context)? {
+		(true, mut outputs) => outputs.pop().into_py_any(py),
+		(false, outputs) => outputs.into_py_any(py),
+	}
+}
+
+/// Render a Markdown string into UTF-8 encoded HTML `bytes`, or a list of
+/// Markdown strings into a list of them, as `render` does, e.g. for the body
+/// of an HTTP response.
 ///
 /// Parameters
 /// ----------
 /// markdown
-///     A list of Markdown strings to render.
+///     A Markdown `str` or UTF-8 `bytes` to render, or an iterable of them.
 /// options
 ///     The Markdown extensions to enable.
+/// threads
+///     The number of threads with which to render, as for `render`.
+/// context
+///     A list with an item for each string of `markdown`, or, for a single
+///     string, the item itself, as for `render`.
 ///
 /// Returns
 /// -------
-/// A list of HTML strings which preserves the indices of `markdown`.
+/// The HTML `bytes` of a single `markdown` string, or, for an iterable, a list
+/// of HTML `bytes` which preserves the indices of `markdown`.
 ///
 /// Raises
 /// ------
 /// BadCallbackError
 ///    If a user callback fails while Markdown is parsed.
+/// TypeError
+///    If `markdown` is neither a string nor an iterable of strings.
+/// ValueError
+///    If `context` doesn't have an item for each string of `markdown`, or an
+///    input exceeds `max_input_bytes` or `max_nesting_depth`.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None, threads = None, context = None))]
+fn render_bytes(
+	py: Python,
+	markdown: &Bound<'_, PyAny>,
+	options: Option<&PyOptions>,
+	threads: Option<usize>,
+	context: Option<PyObject>,
+) -> PyResult<PyObject> {
+	let (single, outputs) = render_html(py, markdown, options, threads, context)?;
+	let mut outputs: Vec<_> = outputs.iter().map(|html| PyBytes::new(py, html.as_bytes())).collect();
+
+	match single {
+		true => outputs.pop().into_py_any(py),
+		false => outputs.into_py_any(py),
+	}
+}
+
+/// Render the arguments of `render`, returning whether `markdown` was a single
+/// string, and the HTML of each of its strings.
+fn render_html(
+	py: Python,
+	markdown: &Bound<'_, PyAny>,
+	options: Option<&PyOptions>,
+	threads: Option<usize>,
+	context: Option<PyObject>,
+) -> PyResult<(bool, Vec<String>)> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let single = markdown.is_instance_of::<PyString>() || markdown.is_instance_of::<PyBytes>();
+
+	let (inputs, context) = match single {
+		true => (vec![input(0, markdown)?], context.map(|context| vec![context])),
+		false => (
+			inputs(markdown)?,
+			context.map(|context| context.extract(py)).transpose()?,
+		),
+	};
+
+	check_context(context.as_ref(), inputs.len())?;
+
+	let outputs = py.allow_threads(move || {
+		let run = || {
+			inputs.par_iter()
+				.enumerate()
+				.map(|(index, buffer)| {
+					let context = context.as_ref().map(|context| &context[index]);
+					let result = render_one(buffer, options, context);
+
+					match single {
+						true => result,
+						false => result.map_err(|err| err.at(index)),
+					}
+				})
+				.collect::<Result<Vec<String>, Fatal>>()
+				.map_err(PyErr::from)
+		};
+
+		match threads {
+			None => run(),
+			Some(0) => Err(PulldownCmarkError::new_err("`threads` must be positive")),
+			Some(threads) => ThreadPoolBuilder::new()
+				.num_threads(threads)
+				.build()
+				.map_err(|err| PulldownCmarkError::new_err(err.to_string()))?
+				.install(run),
+		}
+	})?;
+
+	Ok((single, outputs))
+}
+
+/// Render a list of Markdown strings, keeping failures in place of their HTML.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     An iterable of Markdown `str` or UTF-8 `bytes` to render.
+/// options
+///     The Markdown extensions to enable.
+///
+/// Returns
+/// -------
+/// A list which preserves the indices of `markdown`, holding either the HTML
+/// string for an input or the exception raised while rendering it.
 #[pyfunction]
 #[pyo3(signature = (markdown, options = None))]
-fn render(py: Python, markdown: &Bound<'_, PyList>, options: Option<&PyOptions>) -> PyResult<Vec<String>> {
+fn render_partial(py: Python, markdown: &Bound<'_, PyAny>, options: Option<&PyOptions>) -> PyResult<Vec<PyObject>> {
 	let default = PyOptions::default();
 	let options = options.unwrap_or(&default);
+	let inputs = inputs(markdown)?;
 
-	let inputs: Vec<String> = markdown
-		.iter()
-		.map(|wrapped| wrapped.extract())
-		.collect::<PyResult<_>>()?;
+	let results: Vec<Result<String, Fatal>> = py.allow_threads(move || {
+		inputs.par_iter()
+			.enumerate()
+			.map(|(index, buffer)| render_one(buffer, options, None).map_err(|err| err.at(index)))
+			.collect()
+	});
 
-	py.allow_threads(move || {
+	results.into_iter()
+		.map(|result| match result {
+			Ok(html) => html.into_py_any(py),
+			Err(err) => Ok(PyErr::from(err).into_value(py).into_any()),
+		})
+		.collect()
+}
+
+/// Render a mapping of Markdown strings into a mapping of HTML strings with the
+/// same keys.
+///
+/// Identical Markdown strings are rendered once, and their HTML shared by each
+/// of their keys, so callbacks are called once per distinct document.
+///
+/// Parameters
+/// ----------
+/// documents
+///     A dict of Markdown strings to render, keyed by any hashable IDs.
+/// options
+///     The Markdown extensions to enable.
+///
+/// Returns
+/// -------
+/// A dict of HTML strings with the keys of `documents`, in the same order.
+///
+/// Raises
+/// ------
+/// BadCallbackError
+///    If a user callback fails while Markdown is parsed. The message gives the
+///    key of the failed document.
+#[pyfunction]
+#[pyo3(signature = (documents, options = None))]
+fn render_mapping<'py>(
+	py: Python<'py>,
+	documents: &Bound<'py, PyDict>,
+	options: Option<&PyOptions>,
+) -> PyResult<Bound<'py, PyDict>> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+
+	let mut keys = Vec::with_capacity(documents.len());
+	/* For each key, the index of its Markdown among the distinct inputs. */
+	let mut indices = Vec::with_capacity(documents.len());
+	let mut seen = HashMap::new();
+
+	for (key, value) in documents {
+		let Ok(buffer) = value.extract::<String>() else {
+			return Err(PyTypeError::new_err(format!(
+				"document {} is not a string",
+				key.repr()?
+			)));
+		};
+
+		let next = seen.len();
+		keys.push(key);
+		indices.push(*seen.entry(buffer).or_insert(next));
+	}
+
+	let mut inputs = vec![String::new(); seen.len()];
+
+	for (buffer, index) in seen {
+		inputs[index] = buffer;
+	}
+
+	let results = py.allow_threads(move || {
 		inputs.par_iter()
-			.map(|buffer| {
-				let parser = Parser::new_ext(buffer, options.flags);
-				let iter = EventIter::new(parser, &options.callbacks);
-				let mut output = String::with_capacity(buffer.len());
-				process_results(iter, |events| push_html(&mut output, events)).map(|_| output)
-			})
-			.collect::<Result<Vec<String>, Fatal>>()
-			.map_err(PyErr::from)
-	})
+			.enumerate()
+			.map(|(index, buffer)| render_one(buffer, options, None).map_err(|err| (index, err)))
+			.collect::<Result<Vec<String>, (usize, Fatal)>>()
+	});
+
+	let outputs = match results {
+		Ok(outputs) => outputs,
+		Err((index, err)) => {
+			/* The first key of the document is reported. */
+			let key = &keys[indices.iter().position(|&i| i == index).unwrap()];
+			return Err(err.at_key(key.repr()?.to_string()).into());
+		}
+	};
+
+	let mapping = PyDict::new(py);
+
+	for (key, index) in keys.into_iter().zip(indices) {
+		mapping.set_item(key, &outputs[index])?;
+	}
+
+	Ok(mapping)
 }
 
 /// A configurable Python wrapper around `pulldown-cmark`.
 #[pymodule]
 fn pulldown_cmark(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+	m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+	m.add("PULLDOWN_CMARK_VERSION", env!("PULLDOWN_CMARK_VERSION"))?;
 	m.add_class::<PyOptions>()?;
+	m.add_class::<Cache>()?;
+	m.add_class::<Renderer>()?;
 	m.add("PulldownCmarkError", py.get_type::<PulldownCmarkError>())?;
 	m.add("BadCallbackError", py.get_type::<BadCallbackError>())?;
+	m.add("CannotRenderMathError", py.get_type::<CannotRenderMathError>())?;
+	m.add("CallbackTimeoutError", py.get_type::<CallbackTimeoutError>())?;
+	m.add("CannotConfigMathError", py.get_type::<CannotConfigMathError>())?;
+	m.add("BadTableError", py.get_type::<BadTableError>())?;
+	m.add("BadFrontmatterError", py.get_type::<BadFrontmatterError>())?;
 	m.add_function(wrap_pyfunction!(render, m)?)?;
+	m.add_function(wrap_pyfunction!(render_bytes, m)?)?;
+	m.add_function(wrap_pyfunction!(render_partial, m)?)?;
+	m.add_function(wrap_pyfunction!(render_iter, m)?)?;
+	m.add_function(wrap_pyfunction!(render_stream, m)?)?;
+	m.add_function(wrap_pyfunction!(render_cached, m)?)?;
+	m.add_function(wrap_pyfunction!(render_mapping, m)?)?;
+	m.add_function(wrap_pyfunction!(render_files, m)?)?;
+	m.add_function(wrap_pyfunction!(render_with_sourcemap, m)?)?;
+	m.add_function(wrap_pyfunction!(extract_toc, m)?)?;
+	m.add_function(wrap_pyfunction!(extract_links, m)?)?;
+	m.add_function(wrap_pyfunction!(split_frontmatter, m)?)?;
+	m.add_function(wrap_pyfunction!(parse_frontmatter, m)?)?;
+	m.add_function(wrap_pyfunction!(to_plaintext, m)?)?;
+	m.add_function(wrap_pyfunction!(normalize, m)?)?;
+	m.add_function(wrap_pyfunction!(to_json, m)?)?;
+	m.add_function(wrap_pyfunction!(stats, m)?)?;
+	m.add_function(wrap_pyfunction!(parse_events, m)?)?;
+	m.add_function(wrap_pyfunction!(validate_options, m)?)?;
 	Ok(())
 }
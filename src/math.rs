@@ -0,0 +1,113 @@
+use ::pulldown_cmark::{Event, Options, Parser, Tag};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A custom math delimiter pair: its opening and closing strings, and whether
+/// it delimits display math.
+pub type Delimiter = (String, String, bool);
+
+/// Rewrite math delimited by `delimiters` into `$`/`$$` math, which is the
+/// only syntax `pulldown-cmark` parses.
+///
+/// `pulldown-cmark` treats e.g. `\(` as an escaped `(`, and parses Markdown
+/// inside it, so custom delimiters must be replaced before parsing. Code, raw
+/// HTML, existing math, and front matter are found with a first parse and left
+/// unchanged, as are delimiters preceded by a backslash, spans across a blank
+/// line, and spans which are empty or contain a `$`.
+pub fn normalize<'a>(buffer: &'a str, flags: Options, delimiters: &[Delimiter]) -> Cow<'a, str> {
+	if delimiters.is_empty() || !flags.contains(Options::ENABLE_MATH) {
+		return Cow::Borrowed(buffer);
+	}
+
+	let protected = protected(buffer, flags);
+	let mut output = String::new();
+	let mut copied = 0;
+	let mut position = 0;
+
+	while let Some((start, (open, close, display))) = next_open(buffer, position, delimiters) {
+		let after = start + open.len();
+
+		if let Some(range) = protected.iter().find(|range| range.contains(&start)) {
+			position = range.end;
+			continue;
+		}
+
+		/* A span can't reach into the next protected range. */
+		let limit = protected
+			.iter()
+			.map(|range| range.start)
+			.find(|&s| s >= after)
+			.unwrap_or(buffer.len());
+
+		let Some(length) = buffer[after..limit].find(close.as_str()) else {
+			position = after;
+			continue;
+		};
+
+		let math = buffer[after..after + length].trim();
+
+		if math.is_empty() || math.contains('$') || math.lines().any(|line| line.trim().is_empty()) {
+			position = after;
+			continue;
+		}
+
+		let fence = if *display { "$$" } else { "$" };
+		output.push_str(&buffer[copied..start]);
+		output.push_str(fence);
+		output.push_str(math);
+		output.push_str(fence);
+
+		copied = after + length + close.len();
+		position = copied;
+	}
+
+	if copied == 0 {
+		return Cow::Borrowed(buffer);
+	}
+
+	output.push_str(&buffer[copied..]);
+	Cow::Owned(output)
+}
+
+/// The earliest opening delimiter at or after `position` which isn't escaped
+/// by a backslash.
+fn next_open<'d>(buffer: &str, mut position: usize, delimiters: &'d [Delimiter]) -> Option<(usize, &'d Delimiter)> {
+	loop {
+		let (start, delimiter) = delimiters
+			.iter()
+			.filter_map(|delimiter| {
+				buffer[position..]
+					.find(delimiter.0.as_str())
+					.map(|i| (position + i, delimiter))
+			})
+			.min_by_key(|(start, _)| *start)?;
+
+		if !buffer[..start].ends_with('\\') {
+			return Some((start, delimiter));
+		}
+
+		position = start + delimiter.0.len();
+	}
+}
+
+/// The source ranges, in order, whose contents must not be rewritten.
+fn protected(buffer: &str, flags: Options) -> Vec<Range<usize>> {
+	let mut ranges: Vec<Range<usize>> = Vec::new();
+
+	for (event, range) in Parser::new_ext(buffer, flags).into_offset_iter() {
+		let skip = matches!(
+			event,
+			Event::Start(Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::MetadataBlock(_))
+				| Event::Code(_) | Event::InlineMath(_)
+				| Event::DisplayMath(_) | Event::Html(_)
+				| Event::InlineHtml(_)
+		);
+
+		/* Events nested in a block which is already protected are skipped. */
+		if skip && ranges.last().is_none_or(|last| range.start >= last.end) {
+			ranges.push(range);
+		}
+	}
+
+	ranges
+}
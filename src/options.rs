@@ -1,10 +1,320 @@
-use ::pulldown_cmark::Options;
-use pyo3::prelude::*;
+use crate::error::{CannotConfigMathError, PulldownCmarkError};
+use crate::events::alert;
+use crate::html::StartTag;
+use crate::katex;
+use crate::math::Delimiter;
+use crate::quotes::quotes;
+use ::pulldown_cmark::{BlockQuoteKind, Options, TagEnd};
+use pulldown_cmark_escape::escape_html;
+use pyo3::{
+	exceptions::{PyTypeError, PyValueError},
+	prelude::*,
+	types::PyDict,
+};
+use std::collections::HashMap;
+use std::mem::swap;
+use std::time::Duration;
+
+/// The constructor keyword of each `pulldown-cmark` extension flag.
+const FLAGS: [(&str, Options); 14] = [
+	("tables", Options::ENABLE_TABLES),
+	("footnotes", Options::ENABLE_FOOTNOTES),
+	("strikethrough", Options::ENABLE_STRIKETHROUGH),
+	("tasklists", Options::ENABLE_TASKLISTS),
+	("smart_punctuation", Options::ENABLE_SMART_PUNCTUATION),
+	("heading_attributes", Options::ENABLE_HEADING_ATTRIBUTES),
+	("yaml_style_metadata_blocks", Options::ENABLE_YAML_STYLE_METADATA_BLOCKS),
+	(
+		"pluses_delimited_metadata_blocks",
+		Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS,
+	),
+	("old_footnotes", Options::ENABLE_OLD_FOOTNOTES),
+	("gfm", Options::ENABLE_GFM),
+	("definition_list", Options::ENABLE_DEFINITION_LIST),
+	("superscript", Options::ENABLE_SUPERSCRIPT),
+	("subscript", Options::ENABLE_SUBSCRIPT),
+	("wikilinks", Options::ENABLE_WIKILINKS),
+];
+
+/// The names of the kinds of GFM alerts, for `admonition_titles`.
+const ALERTS: [&str; 5] = ["note", "tip", "important", "warning", "caution"];
+
+/// The anchor link of `anchor_links` without `anchor_template`, as GitHub's.
+const ANCHOR_TEMPLATE: &str = r##"<a class="anchor" aria-hidden="true" href="#{slug}"></a>"##;
+
+/// The optional keyword arguments which each callback may accept.
+const MATH_KEYWORDS: [&str; 1] = ["context"];
+const CODE_KEYWORDS: [&str; 4] = ["attrs", "context", "info", "line"];
+const BROKEN_LINK_KEYWORDS: [&str; 1] = ["context"];
+const WIKILINK_KEYWORDS: [&str; 2] = ["alias", "context"];
+const TASKLIST_MARKER_KEYWORDS: [&str; 1] = ["context"];
+
+/// The `code` callbacks of a dict, and their keywords, by language.
+type Languages = HashMap<String, (PyObject, Vec<&'static str>)>;
 
 #[derive(Default)]
 pub struct Callbacks {
 	pub math: Option<PyObject>,
 	pub code: Option<PyObject>,
+	pub broken_link: Option<PyObject>,
+	pub wikilink: Option<PyObject>,
+	pub tasklist_marker: Option<PyObject>,
+	/// The optional keyword arguments which each callback accepts, so
+	/// callbacks written before a keyword was added keep working.
+	pub math_keywords: Vec<&'static str>,
+	pub code_keywords: Vec<&'static str>,
+	pub broken_link_keywords: Vec<&'static str>,
+	pub wikilink_keywords: Vec<&'static str>,
+	pub tasklist_marker_keywords: Vec<&'static str>,
+	/// The callback for each language and its keywords, if `code` is a dict.
+	pub code_languages: Option<Languages>,
+}
+
+impl Callbacks {
+	/// Each callback with its constructor keyword.
+	fn all(&self) -> [(&'static str, &Option<PyObject>); 5] {
+		[
+			("math", &self.math),
+			("code", &self.code),
+			("broken_link", &self.broken_link),
+			("wikilink", &self.wikilink),
+			("tasklist_marker", &self.tasklist_marker),
+		]
+	}
+
+	/// Each callback which is set, as a new reference.
+	pub fn set(&self, py: Python<'_>) -> Vec<PyObject> {
+		self.all()
+			.into_iter()
+			.filter_map(|(_, callback)| callback.as_ref().map(|callback| callback.clone_ref(py)))
+			.collect()
+	}
+
+	/// The constructor keyword of each callback which is set.
+	fn names(&self) -> Vec<&'static str> {
+		self.all()
+			.into_iter()
+			.filter(|(_, callback)| callback.is_some())
+			.map(|(name, _)| name)
+			.collect()
+	}
+
+	/// The `code` callback for a fence of `language`, and its keywords: the
+	/// callback itself, or if `code` is a dict, the callback for the language
+	/// or the `*` default, if either is set.
+	pub fn code_for(&self, language: Option<&str>) -> Option<(&PyObject, &[&'static str])> {
+		let Some(languages) = &self.code_languages else {
+			return self.code.as_ref().map(|code| (code, self.code_keywords.as_slice()));
+		};
+
+		language.and_then(|language| languages.get(language))
+			.or_else(|| languages.get("*"))
+			.map(|(code, keywords)| (code, keywords.as_slice()))
+	}
+
+	/// Whether both have the same callback objects set, by identity.
+	fn same(&self, other: &Self) -> bool {
+		self.all()
+			.into_iter()
+			.zip(other.all())
+			.all(|((_, callback), (_, other))| match (callback, other) {
+				(Some(callback), Some(other)) => callback.is(other),
+				(None, None) => true,
+				_ => false,
+			})
+	}
+}
+
+/// The keywords of `known` which `callback` accepts, either by name or by
+/// `**kwargs`. A callback without an inspectable signature accepts none.
+fn keywords(py: Python<'_>, callback: Option<&PyObject>, known: &[&'static str]) -> PyResult<Vec<&'static str>> {
+	let Some(callback) = callback else {
+		return Ok(Vec::new());
+	};
+
+	let inspect = py.import("inspect")?;
+
+	let signature = match inspect.call_method1("signature", (callback,)) {
+		Ok(signature) => signature,
+		Err(err) if err.is_instance_of::<PyValueError>(py) || err.is_instance_of::<PyTypeError>(py) => {
+			return Ok(Vec::new());
+		}
+		Err(err) => return Err(err),
+	};
+
+	let parameters = signature.getattr("parameters")?;
+	let var_keyword = inspect.getattr("Parameter")?.getattr("VAR_KEYWORD")?;
+
+	for parameter in parameters.call_method0("values")?.try_iter()? {
+		if parameter?.getattr("kind")?.eq(&var_keyword)? {
+			return Ok(known.to_vec());
+		}
+	}
+
+	let mut accepted = Vec::new();
+
+	for &keyword in known {
+		if parameters.contains(keyword)? {
+			accepted.push(keyword);
+		}
+	}
+
+	Ok(accepted)
+}
+
+/// The callback and keywords of each language of a `code` dict, or `None` if
+/// `code` is a single callback.
+fn languages(py: Python<'_>, code: Option<&PyObject>) -> PyResult<Option<Languages>> {
+	let Some(code) = code.map(|code| code.bind(py)) else {
+		return Ok(None);
+	};
+
+	let Ok(dict) = code.downcast::<PyDict>() else {
+		return Ok(None);
+	};
+
+	let mut languages = HashMap::new();
+
+	for (language, callback) in dict {
+		let Ok(language) = language.extract::<String>() else {
+			return Err(PulldownCmarkError::new_err(
+				"the keys of a `code` dict must be language names",
+			));
+		};
+
+		if !callback.is_callable() {
+			return Err(PulldownCmarkError::new_err(format!(
+				"the `code` callback for `{language}` is not callable"
+			)));
+		}
+
+		let callback = callback.unbind();
+		let keywords = keywords(py, Some(&callback), &CODE_KEYWORDS)?;
+		languages.insert(language, (callback, keywords));
+	}
+
+	Ok(Some(languages))
+}
+
+/// The keyword arguments shared by all callbacks, for a callback which accepts
+/// `keywords`.
+pub fn kwargs<'py>(
+	py: Python<'py>,
+	keywords: &[&'static str],
+	context: Option<&PyObject>,
+) -> PyResult<Bound<'py, PyDict>> {
+	let kwargs = PyDict::new(py);
+
+	if keywords.contains(&"context") {
+		kwargs.set_item("context", context)?;
+	}
+
+	Ok(kwargs)
+}
+
+/// Rendering options implemented by this crate, rather than `pulldown-cmark`.
+#[derive(Default)]
+pub struct Settings {
+	pub auto_heading_ids: bool,
+	pub base_url: Option<String>,
+	pub rewrite_md_links: bool,
+	pub sanitize: bool,
+	pub nofollow_external: bool,
+	pub base_host: Option<String>,
+	pub math_delimiters: Vec<Delimiter>,
+	pub katex: bool,
+	pub heading_offset: i8,
+	pub collect_footnotes: bool,
+	pub strip_comments: bool,
+	pub hardbreaks: bool,
+	pub lazy_images: bool,
+	pub smart_quotes_locale: Option<String>,
+	pub escape_html: bool,
+	pub max_input_bytes: Option<usize>,
+	pub max_nesting_depth: Option<usize>,
+	pub wrapper_tag: Option<String>,
+	pub wrapper_class: Option<String>,
+	pub anchor_links: bool,
+	pub anchor_template: Option<String>,
+	pub dl_class: Option<String>,
+	pub dt_class: Option<String>,
+	pub dd_class: Option<String>,
+	pub admonition_style: Option<String>,
+	pub admonition_titles: Option<HashMap<String, String>>,
+	pub max_blocks: Option<usize>,
+	pub autolink: bool,
+	pub strict_tables: bool,
+	pub compact: bool,
+	pub footnote_prefix: Option<String>,
+	pub allowed_html_tags: Option<Vec<String>>,
+	pub disallowed_html: Option<String>,
+	pub figure_images: bool,
+	pub emoji: bool,
+	pub emoji_map: Option<HashMap<String, String>>,
+	pub accessible: bool,
+	pub callback_timeout: Option<f64>,
+	pub footnotes_title: Option<String>,
+	pub slug_style: Option<String>,
+	pub code_block_class: Option<String>,
+	pub batch_callbacks: bool,
+}
+
+impl Settings {
+	/// The start and end tags of `wrapper_tag`, if it's set.
+	pub fn wrapper(&self) -> Option<(String, String)> {
+		let tag = self.wrapper_tag.as_deref()?;
+		let open = StartTag::new(tag)
+			.attr_if("class", self.wrapper_class.as_deref().unwrap_or_default())
+			.open();
+		Some((format!("{open}\n"), format!("</{tag}>\n")))
+	}
+
+	/// The element name and `class` of a definition list tag, if it has one.
+	pub fn definition_class(&self, end: TagEnd) -> Option<(&'static str, &str)> {
+		let (name, class) = match end {
+			TagEnd::DefinitionList => ("dl", &self.dl_class),
+			TagEnd::DefinitionListTitle => ("dt", &self.dt_class),
+			TagEnd::DefinitionListDefinition => ("dd", &self.dd_class),
+			_ => return None,
+		};
+
+		class.as_deref().map(|class| (name, class))
+	}
+
+	/// The start of the container and title of a GFM alert of `kind`, if it's
+	/// rendered as an admonition per `admonition_style`.
+	pub fn admonition(&self, kind: BlockQuoteKind) -> Option<String> {
+		let (container, title) = match self.admonition_style.as_deref()? {
+			"github" => ("markdown-alert markdown-alert-", "markdown-alert-title"),
+			_ => ("admonition ", "admonition-title"),
+		};
+
+		let name = alert(kind);
+		let default = name[..1].to_uppercase() + &name[1..];
+		let text = self
+			.admonition_titles
+			.as_ref()
+			.and_then(|titles| titles.get(name))
+			.unwrap_or(&default);
+
+		let mut html = StartTag::new("div").attr("class", &format!("{container}{name}")).open();
+		html.push('\n');
+		html.push_str(&StartTag::new("p").attr("class", title).open());
+		/* Writing to a `String` can't fail. */
+		escape_html(&mut html, text).unwrap();
+		html.push_str("</p>\n");
+		Some(html)
+	}
+
+	/// The anchor link to a heading with the ID `slug`, per `anchor_template`.
+	pub fn anchor(&self, slug: &str) -> String {
+		let mut escaped = String::new();
+		/* Writing to a `String` can't fail. */
+		escape_html(&mut escaped, slug).unwrap();
+
+		let template = self.anchor_template.as_deref().unwrap_or(ANCHOR_TEMPLATE);
+		template.replace("{slug}", &escaped)
+	}
 }
 
 /// Wraps `pulldown-cmark::Options` to configure CommonMark extensions.
@@ -41,20 +351,227 @@ pub struct Callbacks {
 ///     Render subscript (`~text~`).
 /// wikilinks
 ///     Render Obsidian-style wikilinks.
+/// auto_heading_ids
+///     Give each heading without an explicit ID a GitHub-style anchor derived
+///     from its text, e.g. `id="foo-bar"`, with repeats suffixed `-1`, `-2`.
+/// base_url
+///     A URL against which to resolve relative link and image destinations,
+///     e.g. `./foo.md` against `https://example.com/docs/` gives
+///     `https://example.com/docs/foo.md`. Destinations with a scheme (such as
+///     `mailto:` or `data:`), protocol-relative, or fragment-only destinations
+///     are left unchanged.
+/// rewrite_md_links
+///     Replace the `.md` extension of relative link destinations with `.html`.
+/// sanitize
+///     Drop raw HTML from the source, and empty `javascript:`, `vbscript:`, and
+///     `data:` link and image destinations, for untrusted input. Output from
+///     callbacks is kept.
+/// nofollow_external
+///     Render links to other hosts with `rel="nofollow noopener"` and
+///     `target="_blank"`. Relative links are never external.
+/// base_host
+///     The host which `nofollow_external` considers internal. Defaults to the
+///     host of `base_url`; without either, every link with a host is external.
+/// math_delimiters
+///     A list of `(open, close, display)` delimiters to pass to `math` besides
+///     `$` and `$$`, e.g. `[("\\(", "\\)", False), ("\\[", "\\]", True)]` for
+///     LaTeX-style math. These have no effect without `math` or `katex`.
+/// katex
+///     Render math into HTML with the `katex` package, instead of a `math`
+///     callback.
+/// heading_offset
+///     Shift every heading level by this amount, clamped to `h1`-`h6`, e.g. `1`
+///     renders `#` as `<h2>` to nest a fragment under a page title.
+/// collect_footnotes
+///     Render footnote definitions in one GitHub-style `<section>` at the end of
+///     the document, in order of first reference, with numbered references and
+///     a `↩` link back to each of them. Unreferenced footnotes are left out.
+///     This has no effect without `footnotes`.
+/// strip_comments
+///     Drop HTML comments from raw HTML in the source, keeping the HTML around
+///     them, e.g. `<div><!-- TODO --></div>` renders as `<div></div>`.
+/// hardbreaks
+///     Render every line break in a paragraph as `<br />`, as GitHub comments
+///     do, rather than as a space.
+/// lazy_images
+///     Render images with `loading="lazy"`, so browsers load them only as they
+///     near the viewport.
+/// smart_quotes_locale
+///     Render quotes in the style of this locale: `en` for “ ” and ‘ ’, `fr`
+///     for « » and ‹ ›, or `de` for „ “ and ‚ ‘. This runs after
+///     `smart_punctuation`, and also replaces any straight quotes it leaves,
+///     or all of them without it. Apostrophes are kept as ’.
+/// escape_html
+///     Render raw HTML from the source as escaped text, e.g. `<b>` as `&lt;b&gt;`,
+///     to show it rather than drop it as `sanitize` does. This takes precedence
+///     over `sanitize` and `strip_comments`. Output from callbacks is kept.
+/// max_input_bytes
+///     Refuse to render an input of more bytes than this with `ValueError`,
+///     before it's parsed, to bound the work done for untrusted input.
+/// max_nesting_depth
+///     Stop rendering an input with `ValueError` when its blocks and inline
+///     markup nest deeper than this, counting each tag, e.g. 3 for the item of
+///     a list in a block quote.
+/// wrapper_tag
+///     The name of an element in which to wrap each rendered document, e.g.
+///     `article`.
+/// wrapper_class
+///     The `class` of the `wrapper_tag` element, e.g. `markdown-body`.
+/// anchor_links
+///     Render a link to each heading with an ID, from `auto_heading_ids` or
+///     `heading_attributes`, before its text, e.g. for a `#` shown on hover.
+/// anchor_template
+///     The raw HTML of each anchor link, in which `{slug}` is replaced with the
+///     escaped ID of the heading. Defaults to
+///     `<a class="anchor" aria-hidden="true" href="#{slug}"></a>`, as GitHub's.
+/// dl_class
+///     The `class` of each `<dl>` element of `definition_list`.
+/// dt_class
+///     The `class` of each `<dt>` element of `definition_list`.
+/// dd_class
+///     The `class` of each `<dd>` element of `definition_list`.
+/// admonition_style
+///     Render the GFM alerts of `gfm`, e.g. `> [!NOTE]`, as titled containers
+///     rather than blockquotes: `admonition` for
+///     `<div class="admonition note"><p class="admonition-title">`, as
+///     Python-Markdown and MkDocs do, or `github` for
+///     `<div class="markdown-alert markdown-alert-note"><p class="markdown-alert-title">`,
+///     as GitHub does. Other blockquotes are rendered as usual.
+/// admonition_titles
+///     The title of each kind of alert, by its lowercase name, e.g.
+///     `{"note": "Remarque"}`. Defaults to the capitalized name, e.g. `Note`.
+/// max_blocks
+///     Stop rendering an input after this many top-level blocks, e.g. for a
+///     quick preview of a long document. Blocks are only cut between each
+///     other, so the HTML is balanced. With `collect_footnotes`, footnotes
+///     defined after the cut are left out.
+/// autolink
+///     Link bare URLs in text, e.g. `https://example.com` and
+///     `www.example.com`, as GitHub does, without the rest of `gfm`. Trailing
+///     punctuation is left out of the link, as is a `)` the URL doesn't open.
+/// strict_tables
+///     Raise `BadTableError` for a table body row with fewer or more cells than
+///     its header has columns, rather than padding or cutting the row, e.g. to
+///     fail a docs build on a broken table.
+/// compact
+///     Minify the HTML by removing the whitespace between block tags, e.g. the
+///     newline after each `</p>`. Whitespace in text, between inline tags, and
+///     inside `<pre>` and `<code>` is kept. Ignored by `render_with_sourcemap`,
+///     whose offsets are into the HTML as `pulldown-cmark` lays it out.
+/// footnote_prefix
+///     Prefix the ID of each footnote and footnote reference, and the links to
+///     them, e.g. `doc3-` for `#doc3-1`, so footnotes don't collide when several
+///     documents are rendered onto one page. With `collect_footnotes`, this
+///     includes the heading of the footnotes section.
+/// allowed_html_tags
+///     Keep only these tags of raw HTML from the source, e.g.
+///     `["b", "i", "sub", "sup"]`, with only their safe attributes, such as
+///     `title` and `href` without a `javascript:` URL. Other tags, comments,
+///     and the content of elements such as `<script>` are dropped or escaped
+///     per `disallowed_html`. `escape_html` and `sanitize` take precedence.
+///     Output from callbacks is kept.
+/// disallowed_html
+///     What `allowed_html_tags` does with other HTML: `drop` it, the default,
+///     or `escape` it to show it as text.
+/// figure_images
+///     Render an image with a title which is alone in its paragraph as a
+///     `<figure>`, with the title as its `<figcaption>` rather than its `title`.
+///     Images inline with text, or without a title, are rendered as usual.
+/// emoji
+///     Replace emoji shortcodes in text with their emoji, e.g. `:smile:` with
+///     `😄`, per GitHub's names. Unknown shortcodes, and code, are left as they
+///     are.
+/// emoji_map
+///     Emoji by shortcode name without colons, e.g. `{"shipit": "🐿️"}`, which
+///     take precedence over `emoji`'s own.
+/// accessible
+///     Add ARIA roles and labels to generated markup: footnote references are
+///     `doc-noteref` links described by the footnotes heading when collected,
+///     or by their definition otherwise, back-references are `doc-backlink`s,
+///     links opened in a new tab by `nofollow_external` say so to screen
+///     readers, and task list checkboxes are labeled with their state.
+/// callback_timeout
+///     The seconds a callback may run before rendering fails with
+///     `CallbackTimeoutError`, e.g. for a `code` callback which runs a slow
+///     process. With a timeout, each callback runs on a daemon thread, which
+///     is left to run on in the background if it times out, as Python code
+///     can't be stopped, until it returns or the interpreter exits. Callbacks
+///     which hold the GIL, such as some C extensions, can't be timed.
+/// footnotes_title
+///     The heading of the `collect_footnotes` section, e.g. `Notes` or a
+///     translation of `Footnotes`, which it defaults to.
+/// slug_style
+///     How heading text becomes an ID: `github`, the default, keeps Unicode
+///     letters as GitHub does, e.g. `café-münü` for `Café Münü`, while `ascii`
+///     transliterates them, e.g. `cafe-munu`, or `zhong-wen` for `中文`.
+/// code_block_class
+///     A class to add to the `<code>` of each fenced code block which isn't
+///     passed to a `code` callback, before its `language-` class, e.g.
+///     `highlight` for `<pre><code class="highlight language-rust">`.
+/// batch_callbacks
+///     Call the `code` and `math` callbacks once per document, with a list of
+///     the arguments of each call, e.g. `[("x = 1\n", "python"), ...]`, to
+///     return a list of what each call would, in order. The GIL is taken once
+///     then, rather than for each code block or formula, but the document is
+///     rendered in two passes, and its HTML only yielded once it's all parsed.
+///     Batched callbacks are passed no keyword but `context`, `code` can't be
+///     a dict, and a batched `math` callback can't reject math with
+///     `CannotRenderMathError`.
 /// math
-///     A callback function with which to filter math delimited by `$` or `$$`,
-///     of signature `def f(buffer: str, display: bool) -> str`.
+///     A callback function with which to filter math delimited by `$`, `$$`, or
+///     `math_delimiters`, of signature `def f(buffer: str, display: bool) -> str`.
+///     It may raise `CannotRenderMathError` to reject invalid math, which is
+///     reraised with the math in the message, or return `None` to render the
+///     math as without a callback, e.g. to leave inline math to a client-side
+///     renderer.
 /// code
 ///     A callback function with which to filter code, of signature
-///     `def f(buffer: str, language: str | None) -> str`.
+///     `def f(buffer: str, language: str | None) -> str`. If it accepts an
+///     `attrs` keyword, it's also passed a dict of the attributes in braces
+///     after the language, e.g. `{"class": "numberLines", "hl_lines": "2 4"}`
+///     for `python {.numberLines hl_lines="2 4"}`. If it accepts a `line`
+///     keyword, it's also passed the line of the first line of code in the
+///     source, counting from 1, e.g. to number lines as in the source file.
+///     If it accepts an `info` keyword, it's also passed the whole info string
+///     of the fence, e.g. `python title="app.py"`, or `None` for indented
+///     code, while `language` is its first word, `python`. Or a dict of such
+///     callbacks by language, e.g. `{"mermaid": f, "python": g}`, with an
+///     optional `"*"` callback for other code; code without a callback is
+///     rendered as usual.
+/// broken_link
+///     A callback function with which to resolve reference links that have no
+///     definition, e.g. `[foo][bar]` without `[bar]: ...`, of signature
+///     `def f(reference: str) -> tuple[str, str] | None`, returning a URL and
+///     title, or `None` to leave the reference as text.
+/// wikilink
+///     A callback function with which to resolve the destination of wikilinks,
+///     e.g. `[[Page Name]]`, of signature
+///     `def f(page: str) -> str | tuple[str, str] | None`, returning a URL, or a
+///     URL and title, for the page, or `None` to link to the page name as is.
+///     If it accepts an `alias` keyword, it's passed the text after the `|` of
+///     `[[Page Name|text]]`, or `None` without one. The link's content is the
+///     alias if there is one, and the page name otherwise. This has no effect
+///     without `wikilinks`.
+/// tasklist_marker
+///     A callback function with which to render task list markers instead of a
+///     disabled checkbox, of signature `def f(checked: bool) -> str`, e.g. to
+///     render `☑` and `☐` in email.
+///
+/// `math`, `code`, and `tasklist_marker` return raw HTML, or a
+/// `(content, is_html)` tuple to return text which is escaped if `is_html` is
+/// false, or a list of either, which are rendered in order, e.g. a figure, its
+/// caption, and its highlighted code. Any callback which accepts a `context`
+/// keyword is passed the `context` item given to `render` for the input being
+/// rendered, or `None`.
 ///
 /// [0]: Front matter blocks are *not* parsed for data. These flags simply let
 ///      the parser skip them without error.
 /// [1]: `pulldown-cmark` will enable `footnotes` if `old-footnotes` is true.
-#[pyclass(name = "Options")]
+#[pyclass(name = "Options", module = "pulldown_cmark")]
 pub struct PyOptions {
 	pub flags: Options,
 	pub callbacks: Callbacks,
+	pub settings: Settings,
 }
 
 #[pymethods]
@@ -79,11 +596,57 @@ impl PyOptions {
 		superscript = false,
 		subscript = false,
 		wikilinks = false,
+		auto_heading_ids = false,
+		base_url = None,
+		rewrite_md_links = false,
+		sanitize = false,
+		nofollow_external = false,
+		base_host = None,
+		math_delimiters = None,
+		katex = false,
+		heading_offset = 0,
+		collect_footnotes = false,
+		strip_comments = false,
+		hardbreaks = false,
+		lazy_images = false,
+		smart_quotes_locale = None,
+		escape_html = false,
+		max_input_bytes = None,
+		max_nesting_depth = None,
+		wrapper_tag = None,
+		wrapper_class = None,
+		anchor_links = false,
+		anchor_template = None,
+		dl_class = None,
+		dt_class = None,
+		dd_class = None,
+		admonition_style = None,
+		admonition_titles = None,
+		max_blocks = None,
+		autolink = false,
+		strict_tables = false,
+		compact = false,
+		footnote_prefix = None,
+		allowed_html_tags = None,
+		disallowed_html = None,
+		figure_images = false,
+		emoji = false,
+		emoji_map = None,
+		accessible = false,
+		callback_timeout = None,
+		footnotes_title = None,
+		slug_style = None,
+		code_block_class = None,
+		batch_callbacks = false,
 		math = None,
 		code = None,
+		broken_link = None,
+		wikilink = None,
+		tasklist_marker = None,
 	))]
 	#[allow(clippy::too_many_arguments)]
 	fn new(
+		py: Python<'_>,
 		tables: bool,
 		footnotes: bool,
 		strikethrough: bool,
@@ -98,47 +661,610 @@ impl PyOptions {
 		superscript: bool,
 		subscript: bool,
 		wikilinks: bool,
+		auto_heading_ids: bool,
+		base_url: Option<String>,
+		rewrite_md_links: bool,
+		sanitize: bool,
+		nofollow_external: bool,
+		base_host: Option<String>,
+		math_delimiters: Option<Vec<Delimiter>>,
+		katex: bool,
+		heading_offset: i8,
+		collect_footnotes: bool,
+		strip_comments: bool,
+		hardbreaks: bool,
+		lazy_images: bool,
+		smart_quotes_locale: Option<String>,
+		escape_html: bool,
+		max_input_bytes: Option<usize>,
+		max_nesting_depth: Option<usize>,
+		wrapper_tag: Option<String>,
+		wrapper_class: Option<String>,
+		anchor_links: bool,
+		anchor_template: Option<String>,
+		dl_class: Option<String>,
+		dt_class: Option<String>,
+		dd_class: Option<String>,
+		admonition_style: Option<String>,
+		admonition_titles: Option<HashMap<String, String>>,
+		max_blocks: Option<usize>,
+		autolink: bool,
+		strict_tables: bool,
+		compact: bool,
+		footnote_prefix: Option<String>,
+		allowed_html_tags: Option<Vec<String>>,
+		disallowed_html: Option<String>,
+		figure_images: bool,
+		emoji: bool,
+		emoji_map: Option<HashMap<String, String>>,
+		accessible: bool,
+		callback_timeout: Option<f64>,
+		footnotes_title: Option<String>,
+		slug_style: Option<String>,
+		code_block_class: Option<String>,
+		batch_callbacks: bool,
 		math: Option<PyObject>,
 		code: Option<PyObject>,
-	) -> Self {
+		broken_link: Option<PyObject>,
+		wikilink: Option<PyObject>,
+		tasklist_marker: Option<PyObject>,
+	) -> PyResult<Self> {
+		let math_delimiters = math_delimiters.unwrap_or_default();
+
+		if math_delimiters
+			.iter()
+			.any(|(open, close, _)| open.is_empty() || close.is_empty())
+		{
+			return Err(PulldownCmarkError::new_err("math delimiters must not be empty"));
+		}
+
+		if katex && math.is_some() {
+			return Err(PulldownCmarkError::new_err("`katex` and `math` are mutually exclusive"));
+		}
+
+		if let Some(locale) = smart_quotes_locale.as_ref().filter(|locale| quotes(locale).is_none()) {
+			return Err(PulldownCmarkError::new_err(format!(
+				"unknown `smart_quotes_locale` `{locale}`: expected `en`, `fr`, or `de`"
+			)));
+		}
+
+		if let Some(tag) = wrapper_tag
+			.as_ref()
+			.filter(|tag| tag.is_empty() || !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+		{
+			return Err(PulldownCmarkError::new_err(format!(
+				"`wrapper_tag` `{tag}` is not a tag name"
+			)));
+		}
+
+		if wrapper_class.is_some() && wrapper_tag.is_none() {
+			return Err(PulldownCmarkError::new_err("`wrapper_class` requires `wrapper_tag`"));
+		}
+
+		if let Some(style) = admonition_style
+			.as_ref()
+			.filter(|style| !["admonition", "github"].contains(&style.as_str()))
+		{
+			return Err(PulldownCmarkError::new_err(format!(
+				"unknown `admonition_style` `{style}`: expected `admonition` or `github`"
+			)));
+		}
+
+		if let Some(titles) = &admonition_titles {
+			if admonition_style.is_none() {
+				return Err(PulldownCmarkError::new_err(
+					"`admonition_titles` requires `admonition_style`",
+				));
+			}
+
+			if let Some(name) = titles.keys().find(|name| !ALERTS.contains(&name.as_str())) {
+				return Err(PulldownCmarkError::new_err(format!(
+					"unknown alert `{name}` in `admonition_titles`: expected one of {}",
+					ALERTS.map(|name| format!("`{name}`")).join(", ")
+				)));
+			}
+		}
+
+		if anchor_template.is_some() && !anchor_links {
+			return Err(PulldownCmarkError::new_err("`anchor_template` requires `anchor_links`"));
+		}
+
+		if emoji_map.is_some() && !emoji {
+			return Err(PulldownCmarkError::new_err("`emoji_map` requires `emoji`"));
+		}
+
+		if let Some(style) = slug_style
+			.as_ref()
+			.filter(|style| !["ascii", "github"].contains(&style.as_str()))
+		{
+			return Err(PulldownCmarkError::new_err(format!(
+				"unknown `slug_style` `{style}`: expected `ascii` or `github`"
+			)));
+		}
+
+		if batch_callbacks && code.is_none() && math.is_none() {
+			return Err(PulldownCmarkError::new_err(
+				"`batch_callbacks` requires a `code` or `math` callback",
+			));
+		}
+
+		if batch_callbacks
+			&& code.as_ref()
+				.is_some_and(|code| code.bind(py).is_instance_of::<PyDict>())
+		{
+			return Err(PulldownCmarkError::new_err(
+				"`batch_callbacks` can't batch a `code` dict",
+			));
+		}
+
+		if footnotes_title.is_some() && !collect_footnotes {
+			return Err(PulldownCmarkError::new_err(
+				"`footnotes_title` requires `collect_footnotes`",
+			));
+		}
+
+		if callback_timeout
+			.is_some_and(|timeout| timeout <= 0.0 || Duration::try_from_secs_f64(timeout).is_err())
+		{
+			return Err(PulldownCmarkError::new_err(
+				"`callback_timeout` must be positive and finite",
+			));
+		}
+
+		if let Some(mode) = &disallowed_html {
+			if !["drop", "escape"].contains(&mode.as_str()) {
+				return Err(PulldownCmarkError::new_err(format!(
+					"unknown `disallowed_html` `{mode}`: expected `drop` or `escape`"
+				)));
+			}
+
+			if allowed_html_tags.is_none() {
+				return Err(PulldownCmarkError::new_err(
+					"`disallowed_html` requires `allowed_html_tags`",
+				));
+			}
+		}
+
+		/* Import `katex` now, so a missing package fails here rather than at
+		 * render time. */
+		if katex {
+			if let Err(err) = katex::module(py) {
+				let wrapped = CannotConfigMathError::new_err("cannot import `katex`");
+				wrapped.set_cause(py, Some(err));
+				return Err(wrapped);
+			}
+		}
+
+		/* The flag keywords, in the order of `FLAGS`, which maps them to flags. */
+		let switches: [bool; FLAGS.len()] = [
+			tables,
+			footnotes,
+			strikethrough,
+			tasklists,
+			smart_punctuation,
+			heading_attributes,
+			yaml_style_metadata_blocks,
+			pluses_delimited_metadata_blocks,
+			old_footnotes,
+			gfm,
+			definition_list,
+			superscript,
+			subscript,
+			wikilinks,
+		];
+
 		let mut flags = Options::empty();
 
-		macro_rules! flag_map {
-			{ $( $switch:expr => $flag:expr),* $(,)? } => {
-				$( if $switch { flags.insert($flag) } )*
-			};
-		}
-
-		flag_map! {
-			tables => Options::ENABLE_TABLES,
-			footnotes => Options::ENABLE_FOOTNOTES,
-			strikethrough => Options::ENABLE_STRIKETHROUGH,
-			tasklists => Options::ENABLE_TASKLISTS,
-			smart_punctuation => Options::ENABLE_SMART_PUNCTUATION,
-			heading_attributes => Options::ENABLE_HEADING_ATTRIBUTES,
-			yaml_style_metadata_blocks => Options::ENABLE_YAML_STYLE_METADATA_BLOCKS,
-			pluses_delimited_metadata_blocks => Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS,
-			old_footnotes => Options::ENABLE_OLD_FOOTNOTES,
-			gfm => Options::ENABLE_GFM,
-			definition_list => Options::ENABLE_DEFINITION_LIST,
-			superscript => Options::ENABLE_SUPERSCRIPT,
-			subscript => Options::ENABLE_SUBSCRIPT,
-			wikilinks => Options::ENABLE_WIKILINKS,
-			math.is_some() => Options::ENABLE_MATH,
+		/* Flags are only inserted, as some contain others, e.g. `gfm`. */
+		for (&(_, flag), switch) in FLAGS.iter().zip(switches) {
+			if switch {
+				flags.insert(flag);
+			}
 		}
 
-		Self {
+		if math.is_some() || katex {
+			flags.insert(Options::ENABLE_MATH);
+		}
+
+		Ok(Self {
 			flags,
-			callbacks: Callbacks { math, code },
+			callbacks: Callbacks {
+				math_keywords: keywords(py, math.as_ref(), &MATH_KEYWORDS)?,
+				code_keywords: keywords(py, code.as_ref(), &CODE_KEYWORDS)?,
+				broken_link_keywords: keywords(py, broken_link.as_ref(), &BROKEN_LINK_KEYWORDS)?,
+				wikilink_keywords: keywords(py, wikilink.as_ref(), &WIKILINK_KEYWORDS)?,
+				tasklist_marker_keywords: keywords(
+					py,
+					tasklist_marker.as_ref(),
+					&TASKLIST_MARKER_KEYWORDS,
+				)?,
+				code_languages: languages(py, code.as_ref())?,
+				math,
+				code,
+				broken_link,
+				wikilink,
+				tasklist_marker,
+			},
+			settings: Settings {
+				auto_heading_ids,
+				base_url,
+				rewrite_md_links,
+				sanitize,
+				nofollow_external,
+				base_host,
+				math_delimiters,
+				katex,
+				heading_offset,
+				collect_footnotes,
+				strip_comments,
+				hardbreaks,
+				lazy_images,
+				smart_quotes_locale,
+				escape_html,
+				max_input_bytes,
+				max_nesting_depth,
+				wrapper_tag,
+				wrapper_class,
+				anchor_links,
+				anchor_template,
+				dl_class,
+				dt_class,
+				dd_class,
+				admonition_style,
+				admonition_titles,
+				max_blocks,
+				autolink,
+				strict_tables,
+				compact,
+				footnote_prefix,
+				allowed_html_tags,
+				disallowed_html,
+				figure_images,
+				emoji,
+				emoji_map,
+				accessible,
+				callback_timeout,
+				footnotes_title,
+				slug_style,
+				code_block_class,
+				batch_callbacks,
+			},
+		})
+	}
+
+	/// Create a new `Options` instance from a mapping of constructor keywords.
+	///
+	/// The mapping is passed to the constructor as is, so it accepts the same
+	/// extension names and callbacks.
+	///
+	/// Raises
+	/// ------
+	/// PulldownCmarkError
+	///     If a key is not a constructor keyword, or a value has the wrong type.
+	#[staticmethod]
+	fn from_dict<'py>(mapping: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyOptions>> {
+		let py = mapping.py();
+
+		let options = py.get_type::<PyOptions>().call((), Some(mapping)).map_err(|err| {
+			if err.is_instance_of::<PyTypeError>(py) {
+				let wrapped = PulldownCmarkError::new_err(err.value(py).to_string());
+				wrapped.set_cause(py, Some(err));
+				wrapped
+			} else {
+				err
+			}
+		})?;
+
+		Ok(options.downcast_into::<PyOptions>()?)
+	}
+
+	/// Create a new `Options` instance with the GitHub-Flavored Markdown set:
+	/// `tables`, `footnotes`, `strikethrough`, `tasklists`,
+	/// `heading_attributes`, and `gfm`.
+	#[staticmethod]
+	fn gfm_extensions() -> Self {
+		Self::from(
+			Options::ENABLE_TABLES
+				| Options::ENABLE_FOOTNOTES | Options::ENABLE_STRIKETHROUGH
+				| Options::ENABLE_TASKLISTS | Options::ENABLE_HEADING_ATTRIBUTES
+				| Options::ENABLE_GFM,
+		)
+	}
+
+	/// Create a new `Options` instance with every extension enabled, except
+	/// `yaml_style_metadata_blocks`, `pluses_delimited_metadata_blocks`, and
+	/// `old_footnotes`, which replaces the syntax of `footnotes`.
+	#[staticmethod]
+	fn all_extensions() -> Self {
+		let skipped = [
+			Options::ENABLE_YAML_STYLE_METADATA_BLOCKS,
+			Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS,
+			Options::ENABLE_OLD_FOOTNOTES,
+		];
+
+		let flags = FLAGS
+			.iter()
+			.filter(|(_, flag)| !skipped.contains(flag))
+			.fold(Options::empty(), |flags, (_, flag)| flags | *flag);
+
+		Self::from(flags)
+	}
+
+	/// The constructor keywords of the enabled extensions, including those
+	/// implied by another, e.g. `footnotes` by `old_footnotes`.
+	#[getter]
+	fn extensions(&self) -> Vec<&'static str> {
+		FLAGS.iter()
+			.filter(|(_, flag)| self.flags.contains(*flag))
+			.map(|(name, _)| *name)
+			.collect()
+	}
+
+	/// The constructor keywords of the callbacks which are set, e.g.
+	/// `["math", "code"]`.
+	#[getter]
+	fn callbacks(&self) -> Vec<&'static str> {
+		self.callbacks.names()
+	}
+
+	/// Compare by value: the same extensions and settings, and the same
+	/// callback objects.
+	fn __eq__(&self, py: Python, other: PyRef<'_, Self>) -> PyResult<bool> {
+		Ok(self.flags == other.flags
+			&& self.callbacks.same(&other.callbacks)
+			&& self.kwargs(py)?.eq(other.kwargs(py)?)?)
+	}
+
+	/// Hash the extension flags, which equal instances share.
+	fn __hash__(&self) -> u64 {
+		self.flags.bits().into()
+	}
+
+	fn __repr__(&self, py: Python) -> PyResult<String> {
+		let mut fields = Vec::new();
+
+		for (name, value) in self.kwargs(py)? {
+			fields.push(format!("{name}={}", value.repr()?));
 		}
+
+		for name in self.callbacks.names() {
+			fields.push(format!("{name}=<callback>"));
+		}
+
+		Ok(format!("Options({})", fields.join(", ")))
+	}
+
+	/// Pickle the constructor keywords. Callbacks generally can't be pickled,
+	/// so an instance with callbacks refuses to be pickled at all.
+	fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+		if !self.callbacks.names().is_empty() {
+			return Err(PulldownCmarkError::new_err("cannot pickle `Options` with callbacks"));
+		}
+
+		self.kwargs(py)
+	}
+
+	fn __setstate__(&mut self, state: &Bound<'_, PyDict>) -> PyResult<()> {
+		let options = state.py().get_type::<PyOptions>().call((), Some(state))?;
+		swap(self, &mut *options.downcast_into::<PyOptions>()?.borrow_mut());
+		Ok(())
 	}
 }
 
-impl Default for PyOptions {
-	fn default() -> Self {
+impl PyOptions {
+	/// A key which is equal for options which render alike: the constructor
+	/// keywords, and the identity of each callback.
+	pub fn key(&self, py: Python) -> PyResult<String> {
+		let mut key = self.kwargs(py)?.repr()?.to_string();
+
+		for (name, callback) in self.callbacks.all() {
+			if let Some(callback) = callback {
+				key.push_str(&format!(" {name}={:p}", callback.as_ptr()));
+			}
+		}
+
+		Ok(key)
+	}
+
+	/// The constructor keywords, other than callbacks, which differ from their
+	/// defaults.
+	fn kwargs<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+		let kwargs = PyDict::new(py);
+
+		for (name, flag) in FLAGS {
+			/* Skip flags implied by another enabled flag, e.g. `footnotes` by
+			 * `old_footnotes`; passing the larger flag alone round-trips. */
+			let implied = FLAGS.iter().any(|&(_, other)| {
+				other != flag && other.contains(flag) && self.flags.contains(other)
+			});
+
+			if self.flags.contains(flag) && !implied {
+				kwargs.set_item(name, true)?;
+			}
+		}
+
+		if self.settings.auto_heading_ids {
+			kwargs.set_item("auto_heading_ids", true)?;
+		}
+
+		if let Some(base_url) = &self.settings.base_url {
+			kwargs.set_item("base_url", base_url)?;
+		}
+
+		if self.settings.rewrite_md_links {
+			kwargs.set_item("rewrite_md_links", true)?;
+		}
+
+		if self.settings.sanitize {
+			kwargs.set_item("sanitize", true)?;
+		}
+
+		if self.settings.nofollow_external {
+			kwargs.set_item("nofollow_external", true)?;
+		}
+
+		if let Some(base_host) = &self.settings.base_host {
+			kwargs.set_item("base_host", base_host)?;
+		}
+
+		if !self.settings.math_delimiters.is_empty() {
+			kwargs.set_item("math_delimiters", &self.settings.math_delimiters)?;
+		}
+
+		if self.settings.katex {
+			kwargs.set_item("katex", true)?;
+		}
+
+		if self.settings.heading_offset != 0 {
+			kwargs.set_item("heading_offset", self.settings.heading_offset)?;
+		}
+
+		if self.settings.collect_footnotes {
+			kwargs.set_item("collect_footnotes", true)?;
+		}
+
+		if self.settings.strip_comments {
+			kwargs.set_item("strip_comments", true)?;
+		}
+
+		if self.settings.hardbreaks {
+			kwargs.set_item("hardbreaks", true)?;
+		}
+
+		if self.settings.lazy_images {
+			kwargs.set_item("lazy_images", true)?;
+		}
+
+		if let Some(smart_quotes_locale) = &self.settings.smart_quotes_locale {
+			kwargs.set_item("smart_quotes_locale", smart_quotes_locale)?;
+		}
+
+		if self.settings.escape_html {
+			kwargs.set_item("escape_html", true)?;
+		}
+
+		if let Some(max_input_bytes) = self.settings.max_input_bytes {
+			kwargs.set_item("max_input_bytes", max_input_bytes)?;
+		}
+
+		if let Some(max_nesting_depth) = self.settings.max_nesting_depth {
+			kwargs.set_item("max_nesting_depth", max_nesting_depth)?;
+		}
+
+		if let Some(wrapper_tag) = &self.settings.wrapper_tag {
+			kwargs.set_item("wrapper_tag", wrapper_tag)?;
+		}
+
+		if let Some(wrapper_class) = &self.settings.wrapper_class {
+			kwargs.set_item("wrapper_class", wrapper_class)?;
+		}
+
+		if self.settings.anchor_links {
+			kwargs.set_item("anchor_links", true)?;
+		}
+
+		if let Some(anchor_template) = &self.settings.anchor_template {
+			kwargs.set_item("anchor_template", anchor_template)?;
+		}
+
+		for (name, class) in [
+			("dl_class", &self.settings.dl_class),
+			("dt_class", &self.settings.dt_class),
+			("dd_class", &self.settings.dd_class),
+		] {
+			if let Some(class) = class {
+				kwargs.set_item(name, class)?;
+			}
+		}
+
+		if let Some(admonition_style) = &self.settings.admonition_style {
+			kwargs.set_item("admonition_style", admonition_style)?;
+		}
+
+		if let Some(admonition_titles) = &self.settings.admonition_titles {
+			kwargs.set_item("admonition_titles", admonition_titles)?;
+		}
+
+		if let Some(max_blocks) = self.settings.max_blocks {
+			kwargs.set_item("max_blocks", max_blocks)?;
+		}
+
+		if self.settings.autolink {
+			kwargs.set_item("autolink", true)?;
+		}
+
+		if self.settings.strict_tables {
+			kwargs.set_item("strict_tables", true)?;
+		}
+
+		if self.settings.compact {
+			kwargs.set_item("compact", true)?;
+		}
+
+		if let Some(footnote_prefix) = &self.settings.footnote_prefix {
+			kwargs.set_item("footnote_prefix", footnote_prefix)?;
+		}
+
+		if let Some(allowed_html_tags) = &self.settings.allowed_html_tags {
+			kwargs.set_item("allowed_html_tags", allowed_html_tags)?;
+		}
+
+		if let Some(disallowed_html) = &self.settings.disallowed_html {
+			kwargs.set_item("disallowed_html", disallowed_html)?;
+		}
+
+		if self.settings.figure_images {
+			kwargs.set_item("figure_images", true)?;
+		}
+
+		if self.settings.emoji {
+			kwargs.set_item("emoji", true)?;
+		}
+
+		if let Some(emoji_map) = &self.settings.emoji_map {
+			kwargs.set_item("emoji_map", emoji_map)?;
+		}
+
+		if self.settings.accessible {
+			kwargs.set_item("accessible", true)?;
+		}
+
+		if let Some(timeout) = self.settings.callback_timeout {
+			kwargs.set_item("callback_timeout", timeout)?;
+		}
+
+		if let Some(footnotes_title) = &self.settings.footnotes_title {
+			kwargs.set_item("footnotes_title", footnotes_title)?;
+		}
+
+		if let Some(slug_style) = &self.settings.slug_style {
+			kwargs.set_item("slug_style", slug_style)?;
+		}
+
+		if let Some(code_block_class) = &self.settings.code_block_class {
+			kwargs.set_item("code_block_class", code_block_class)?;
+		}
+
+		if self.settings.batch_callbacks {
+			kwargs.set_item("batch_callbacks", true)?;
+		}
+
+		Ok(kwargs)
+	}
+}
+
+impl From<Options> for PyOptions {
+	fn from(flags: Options) -> Self {
 		Self {
-			flags: Options::empty(),
+			flags,
 			callbacks: Callbacks::default(),
+			settings: Settings::default(),
 		}
 	}
 }
+
+impl Default for PyOptions {
+	fn default() -> Self {
+		Self::from(Options::empty())
+	}
+}
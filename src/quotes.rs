@@ -0,0 +1,53 @@
+/// The opening and closing double quotes, then single quotes, of a locale.
+pub struct Quotes {
+	double: (char, char),
+	single: (char, char),
+}
+
+/// The quotes of `locale`, by its language subtag, e.g. `de` for `de-CH`.
+pub fn quotes(locale: &str) -> Option<Quotes> {
+	let language = locale.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+
+	let (double, single) = match language.as_str() {
+		"en" => (('“', '”'), ('‘', '’')),
+		"fr" => (('«', '»'), ('‹', '›')),
+		"de" => (('„', '“'), ('‚', '‘')),
+		_ => return None,
+	};
+
+	Some(Quotes { double, single })
+}
+
+/// Replace the quotes in `text`, curly from `smart_punctuation` or straight,
+/// with `quotes`.
+///
+/// Whether a quote opens or closes depends on the character before it, so
+/// `previous` is the character before `text`, and is updated to the last
+/// character of the output. A single quote between letters is an apostrophe,
+/// which is kept as `’`.
+pub fn localize(text: &str, quotes: &Quotes, previous: &mut char) -> String {
+	let mut output = String::with_capacity(text.len());
+	let mut chars = text.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		let next = chars.peek().copied();
+		let opens = previous.is_whitespace()
+			|| "([{-–—".contains(*previous)
+			|| [quotes.double.0, quotes.single.0].contains(previous);
+		let apostrophe = previous.is_alphanumeric() && next.is_some_and(char::is_alphanumeric);
+
+		let quote = match c {
+			'“' | '”' | '"' if opens => quotes.double.0,
+			'“' | '”' | '"' => quotes.double.1,
+			'‘' | '’' | '\'' if apostrophe => '’',
+			'‘' | '’' | '\'' if opens => quotes.single.0,
+			'‘' | '’' | '\'' => quotes.single.1,
+			c => c,
+		};
+
+		output.push(quote);
+		*previous = quote;
+	}
+
+	output
+}
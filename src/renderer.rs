@@ -0,0 +1,72 @@
+use crate::options::PyOptions;
+use crate::{input, render_into};
+use pyo3::{prelude::*, types::PyString};
+use std::mem::take;
+use std::sync::Mutex;
+
+/// A renderer of one Markdown string at a time with fixed options, which keeps
+/// its HTML buffer between calls, e.g. to render many small documents in a
+/// server loop without allocating a buffer for each.
+///
+/// The buffer grows to fit the largest document rendered, and is never shrunk.
+/// It's safe to share between threads, though concurrent calls allocate their
+/// own buffers while another holds the shared one.
+#[pyclass(name = "Renderer", module = "pulldown_cmark", frozen)]
+pub struct Renderer {
+	options: Py<PyOptions>,
+	buffer: Mutex<String>,
+}
+
+#[pymethods]
+impl Renderer {
+	#[new]
+	#[pyo3(signature = (options = None))]
+	fn new(py: Python, options: Option<Py<PyOptions>>) -> PyResult<Self> {
+		let options = match options {
+			Some(options) => options,
+			None => Py::new(py, PyOptions::default())?,
+		};
+
+		Ok(Self {
+			options,
+			buffer: Mutex::new(String::new()),
+		})
+	}
+
+	/// Render a Markdown string into HTML, as `render` does a single string.
+	///
+	/// Parameters
+	/// ----------
+	/// markdown
+	///     A Markdown `str` or UTF-8 `bytes` to render.
+	/// context
+	///     The `context` keyword to pass to each callback that accepts one.
+	///
+	/// Returns
+	/// -------
+	/// The HTML string.
+	#[pyo3(signature = (markdown, context = None))]
+	fn render<'py>(
+		&self,
+		py: Python<'py>,
+		markdown: &Bound<'py, PyAny>,
+		context: Option<PyObject>,
+	) -> PyResult<Bound<'py, PyString>> {
+		let markdown = input(0, markdown)?;
+		let options = self.options.borrow(py);
+		let options: &PyOptions = &options;
+
+		/* The buffer is taken rather than locked while rendering, as callbacks
+		 * take the GIL, which a caller waiting on the lock would hold. */
+		let (buffer, result) = py.allow_threads(|| {
+			let mut buffer = take(&mut *self.buffer.lock().unwrap());
+			buffer.clear();
+			let result = render_into(&mut buffer, &markdown, options, context.as_ref());
+			(buffer, result)
+		});
+
+		let html = result.map(|()| PyString::new(py, &buffer));
+		*self.buffer.lock().unwrap() = buffer;
+		Ok(html?)
+	}
+}
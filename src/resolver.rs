@@ -0,0 +1,61 @@
+use crate::error::Fatal;
+use crate::options::{Callbacks, kwargs};
+use crate::timeout::call;
+use ::pulldown_cmark::{BrokenLink, BrokenLinkCallback, CowStr};
+use pyo3::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// The first error raised by a callback which can't return one itself, kept
+/// until the event iterator can report it.
+pub type Deferred = Rc<Cell<Option<Fatal>>>;
+
+/// Resolves broken reference links through the `broken_link` callback.
+pub struct Resolver<'c> {
+	callback: &'c PyObject,
+	keywords: &'c [&'static str],
+	context: Option<&'c PyObject>,
+	timeout: Option<f64>,
+	error: Deferred,
+}
+
+impl<'c> Resolver<'c> {
+	/// Create a resolver if a `broken_link` callback is set, along with the
+	/// handle through which it reports errors.
+	pub fn new(
+		callbacks: &'c Callbacks,
+		context: Option<&'c PyObject>,
+		timeout: Option<f64>,
+	) -> (Option<Self>, Deferred) {
+		let error = Deferred::default();
+		let resolver = callbacks.broken_link.as_ref().map(|callback| Self {
+			callback,
+			keywords: &callbacks.broken_link_keywords,
+			context,
+			timeout,
+			error: Rc::clone(&error),
+		});
+
+		(resolver, error)
+	}
+}
+
+impl<'input> BrokenLinkCallback<'input> for Resolver<'_> {
+	fn handle_broken_link(&mut self, link: BrokenLink<'input>) -> Option<(CowStr<'input>, CowStr<'input>)> {
+		let result = Python::with_gil(|py| -> Result<_, Fatal> {
+			let kwargs = kwargs(py, self.keywords, self.context)?;
+			let result = call(py, self.callback, (link.reference.as_ref(),), &kwargs, self.timeout)?;
+			Ok(result.extract::<Option<(String, String)>>(py)?)
+		});
+
+		match result {
+			Ok(resolved) => resolved.map(|(url, title)| (url.into(), title.into())),
+			Err(err) => {
+				/* Only the first error is reported, as later ones may be caused by it. */
+				let first = self.error.take().unwrap_or(err);
+				self.error.set(Some(first));
+				None
+			}
+		}
+	}
+}
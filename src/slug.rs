@@ -0,0 +1,63 @@
+use deunicode::deunicode;
+use std::collections::HashMap;
+
+/// Generates GitHub-style anchor slugs, which are unique within a document.
+#[derive(Default)]
+pub struct Slugger {
+	occurrences: HashMap<String, usize>,
+	/// Whether to transliterate text to ASCII, per `slug_style`.
+	ascii: bool,
+}
+
+impl Slugger {
+	pub fn new(style: Option<&str>) -> Self {
+		Self {
+			ascii: style == Some("ascii"),
+			..Self::default()
+		}
+	}
+
+	/// Return a unique slug for `text`, suffixing repeats with `-1`, `-2`, etc.
+	pub fn slug(&mut self, text: &str) -> String {
+		let original = match self.ascii {
+			true => slugify(&deunicode(text)),
+			false => slugify(text),
+		};
+		let mut slug = original.clone();
+
+		while self.occurrences.contains_key(&slug) {
+			let count = self.occurrences.get_mut(&original).unwrap();
+			*count += 1;
+			slug = format!("{original}-{count}");
+		}
+
+		self.occurrences.insert(slug.clone(), 0);
+		slug
+	}
+
+	/// Return the anchor of a heading: its explicit ID, which is reserved so
+	/// later slugs don't collide with it, or else a slug of its text.
+	pub fn anchor(&mut self, id: Option<&str>, text: &str) -> String {
+		match id {
+			Some(id) => {
+				self.occurrences.entry(String::from(id)).or_insert(0);
+				String::from(id)
+			}
+			None => self.slug(text),
+		}
+	}
+}
+
+/// Lowercase `text`, strip punctuation, and replace spaces with hyphens.
+fn slugify(text: &str) -> String {
+	text.trim()
+		.to_lowercase()
+		.chars()
+		.filter_map(|c| match c {
+			' ' => Some('-'),
+			'-' | '_' => Some(c),
+			c if c.is_alphanumeric() => Some(c),
+			_ => None,
+		})
+		.collect()
+}
@@ -0,0 +1,153 @@
+use crate::error::Fatal;
+use crate::iter::EventIter;
+use crate::options::PyOptions;
+use crate::{check_size, math};
+use ::pulldown_cmark::{Event, html::write_html_fmt};
+use pyo3::prelude::*;
+use std::cell::Cell;
+use std::fmt;
+
+/// `(source_start, source_end, output_offset)` for each top-level block.
+type SourceMap = Vec<(usize, usize, usize)>;
+
+/// Render a Markdown string into HTML, with a source map of its blocks, for
+/// e.g. scrolling a preview in sync with an editor.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     The Markdown string to render.
+/// options
+///     The Markdown extensions to enable.
+///
+/// Returns
+/// -------
+/// A `(html, sourcemap)` tuple, where `sourcemap` is a list of
+/// `(source_start, source_end, output_offset)` tuples in document order, one
+/// for each top-level block, giving the byte range of the block in the UTF-8
+/// encoding of `markdown`, and the byte offset at which its HTML starts in the
+/// UTF-8 encoding of `html`. Nested blocks are covered by their top-level
+/// block. With `math_delimiters`, source offsets are those of the Markdown
+/// after its math is rewritten to `$` and `$$`.
+///
+/// Raises
+/// ------
+/// BadCallbackError
+///    If a user callback fails while Markdown is parsed.
+/// BadTableError
+///    If `strict_tables` is set and a table row doesn't have one cell for each
+///    column of its header.
+/// ValueError
+///    If `markdown` exceeds `max_input_bytes` or `max_nesting_depth`.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None))]
+pub fn render_with_sourcemap(py: Python, markdown: &str, options: Option<&PyOptions>) -> PyResult<(String, SourceMap)> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+
+	py.allow_threads(|| {
+		check_size(markdown, options)?;
+		let buffer = math::normalize(markdown, options.flags, &options.settings.math_delimiters);
+		let wrapper = options.settings.wrapper();
+		let mut output = String::with_capacity(buffer.len());
+
+		if let Some((open, _)) = &wrapper {
+			output.push_str(open);
+		}
+
+		let length = Cell::new(output.len());
+
+		let mut blocks = Blocks {
+			iter: EventIter::new(&buffer, options, None),
+			length: &length,
+			depth: 0,
+			sourcemap: Vec::new(),
+			error: None,
+		};
+
+		/* Writing to a `String` can't fail. */
+		write_html_fmt(
+			Counter {
+				output: &mut output,
+				length: &length,
+			},
+			&mut blocks,
+		)
+		.unwrap();
+
+		if let Some(err) = blocks.error {
+			return Err(err.into());
+		}
+
+		if let Some((_, close)) = &wrapper {
+			output.push_str(close);
+		}
+
+		Ok((output, blocks.sourcemap))
+	})
+}
+
+/// Writes HTML into a string, publishing its length so far.
+struct Counter<'a> {
+	output: &'a mut String,
+	length: &'a Cell<usize>,
+}
+
+impl fmt::Write for Counter<'_> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.output.push_str(s);
+		self.length.set(self.output.len());
+		Ok(())
+	}
+}
+
+/// Wraps `EventIter` to record the source range and output offset of each
+/// top-level block as it's written, stopping at the first error.
+struct Blocks<'a, 'p, 'c> {
+	iter: EventIter<'p, 'c>,
+	length: &'a Cell<usize>,
+	depth: usize,
+	sourcemap: SourceMap,
+	error: Option<Fatal>,
+}
+
+impl<'p> Iterator for Blocks<'_, 'p, '_> {
+	type Item = Event<'p>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let event = match self.iter.next()? {
+			Ok(event) => event,
+			Err(err) => {
+				self.error = Some(err);
+				return None;
+			}
+		};
+
+		/* Callback output, such as a highlighted code block, stands alone at the
+		 * top level, rather than between a start and an end. */
+		let top = self.depth == 0 && !matches!(event, Event::End(_));
+
+		/* An end may have lost its start to raw HTML, so the depth saturates
+		 * rather than trusting the events to balance. */
+		match event {
+			Event::Start(_) => self.depth += 1,
+			Event::End(_) => self.depth = self.depth.saturating_sub(1),
+			_ => {}
+		}
+
+		let range = self.iter.range();
+
+		/* Events within the range of the last entry, such as the raw HTML of a
+		 * footnote section or the text of a block started by raw HTML, are
+		 * covered by it. */
+		if top && self
+			.sourcemap
+			.last()
+			.is_none_or(|&(start, end, _)| range.start < start || range.end > end)
+		{
+			self.sourcemap.push((range.start, range.end, self.length.get()));
+		}
+
+		Some(event)
+	}
+}
@@ -0,0 +1,234 @@
+use crate::iter::EventIter;
+use crate::options::PyOptions;
+use crate::{check_size, input, markdown_iter, math, render_one};
+use ::pulldown_cmark::html::write_html_fmt;
+use itertools::process_results;
+use pyo3::{
+	exceptions::{PyTypeError, PyUnicodeDecodeError, PyValueError},
+	prelude::*,
+	types::{PyBytes, PyIterator, PyString},
+};
+use std::fmt;
+use std::mem::take;
+
+/// The size of each read from `reader`, in characters or bytes, and of each
+/// write to `writer`, in UTF-8 bytes, give or take an event's HTML.
+const CHUNK: usize = 64 * 1024;
+
+/// An iterator which renders each Markdown string only when it is requested.
+#[pyclass(name = "RenderIter", module = "pulldown_cmark")]
+pub struct RenderIter {
+	inputs: Py<PyIterator>,
+	options: Py<PyOptions>,
+	index: usize,
+}
+
+#[pymethods]
+impl RenderIter {
+	fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+		slf
+	}
+
+	fn __next__(&mut self, py: Python) -> PyResult<Option<String>> {
+		let Some(item) = self.inputs.bind(py).clone().next() else {
+			return Ok(None);
+		};
+
+		let buffer = input(self.index, &item?)?;
+		let options = self.options.borrow(py);
+		let options: &PyOptions = &options;
+		let index = self.index;
+		self.index += 1;
+
+		let html = py.allow_threads(|| render_one(&buffer, options, None).map_err(|err| err.at(index)))?;
+		Ok(Some(html))
+	}
+}
+
+/// Render an iterable of Markdown strings lazily, one at a time.
+///
+/// Unlike `render`, inputs are consumed and rendered only as the iterator is
+/// advanced, so neither the inputs nor the outputs are held in memory at once.
+/// Inputs are rendered sequentially.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     An iterable of Markdown `str` or UTF-8 `bytes` to render.
+/// options
+///     The Markdown extensions to enable.
+///
+/// Returns
+/// -------
+/// An iterator of HTML strings in the order of `markdown`.
+///
+/// Raises
+/// ------
+/// BadCallbackError
+///    When advanced, if a user callback fails while Markdown is parsed.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None))]
+pub fn render_iter(py: Python, markdown: &Bound<'_, PyAny>, options: Option<Py<PyOptions>>) -> PyResult<RenderIter> {
+	let options = match options {
+		Some(options) => options,
+		None => Py::new(py, PyOptions::default())?,
+	};
+
+	Ok(RenderIter {
+		inputs: markdown_iter(markdown)?.unbind(),
+		options,
+		index: 0,
+	})
+}
+
+/// Render a Markdown document from a file object into another, e.g. for a
+/// document too large to keep its HTML in memory as one string.
+///
+/// CommonMark resolves link references across the whole document, so the
+/// input is read in full before it's parsed, and is held in memory with its
+/// parse state. The HTML is written as it's rendered, in chunks of about 64
+/// KiB, so it's never held at once, unless `compact` is set, or footnotes are
+/// held for the end per `collect_footnotes`.
+///
+/// Parameters
+/// ----------
+/// reader
+///     An object with a `read(size)` method which returns Markdown `str` or
+///     UTF-8 `bytes`, and an empty string at the end, e.g. an open file.
+/// writer
+///     An object with a `write(str)` method, e.g. a file open for text.
+/// options
+///     The Markdown extensions to enable.
+///
+/// Raises
+/// ------
+/// BadCallbackError
+///    If a user callback fails while Markdown is parsed. HTML before the
+///    failure may have been written.
+/// ValueError
+///    If the input isn't valid UTF-8, or exceeds `max_input_bytes` or
+///    `max_nesting_depth`.
+#[pyfunction]
+#[pyo3(signature = (reader, writer, options = None))]
+pub fn render_stream(
+	reader: &Bound<'_, PyAny>,
+	writer: &Bound<'_, PyAny>,
+	options: Option<&PyOptions>,
+) -> PyResult<()> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let markdown = read(reader)?;
+	let mut writer = Writer {
+		writer: writer.clone(),
+		buffer: String::new(),
+		error: None,
+	};
+
+	if options.settings.compact {
+		let html = render_one(&markdown, options, None)?;
+		let mut rest = html.as_str();
+
+		/* `compact` needs the whole output, which is still written in chunks. */
+		while !rest.is_empty() {
+			let mut end = rest.len().min(CHUNK);
+
+			while !rest.is_char_boundary(end) {
+				end += 1;
+			}
+
+			writer.writer.call_method1("write", (&rest[..end],))?;
+			rest = &rest[end..];
+		}
+
+		return Ok(());
+	}
+
+	check_size(&markdown, options)?;
+	let buffer = math::normalize(&markdown, options.flags, &options.settings.math_delimiters);
+	let iter = EventIter::new(&buffer, options, None);
+	let wrapper = options.settings.wrapper();
+
+	if let Some((open, _)) = &wrapper {
+		writer.buffer.push_str(open);
+	}
+
+	match process_results(iter, |events| write_html_fmt(&mut writer, events)) {
+		Err(err) => return Err(err.into()),
+		/* Only a failed `write` fails the writer. */
+		Ok(Err(_)) => return Err(writer.error.take().unwrap()),
+		Ok(Ok(())) => {}
+	}
+
+	if let Some((_, close)) = &wrapper {
+		writer.buffer.push_str(close);
+	}
+
+	writer.flush()
+}
+
+/// Read a Markdown document from `reader` in chunks of `str` or UTF-8 `bytes`.
+fn read(reader: &Bound<'_, PyAny>) -> PyResult<String> {
+	let mut bytes = Vec::new();
+
+	loop {
+		let chunk = reader.call_method1("read", (CHUNK,))?;
+
+		if let Ok(chunk) = chunk.downcast::<PyBytes>() {
+			match chunk.as_bytes() {
+				[] => break,
+				chunk => bytes.extend_from_slice(chunk),
+			}
+		} else if let Ok(chunk) = chunk.downcast::<PyString>() {
+			match chunk.to_str()? {
+				"" => break,
+				chunk => bytes.extend_from_slice(chunk.as_bytes()),
+			}
+		} else {
+			return Err(PyTypeError::new_err(format!(
+				"`reader.read` returned {}, not str or bytes",
+				chunk.get_type().name()?
+			)));
+		}
+	}
+
+	String::from_utf8(bytes).or_else(|err| {
+		let py = reader.py();
+		let cause = PyUnicodeDecodeError::new_utf8(py, err.as_bytes(), err.utf8_error())?;
+		let wrapped = PyValueError::new_err("input is not valid UTF-8");
+		wrapped.set_cause(py, Some(PyErr::from_value(cause.into_any())));
+		Err(wrapped)
+	})
+}
+
+/// Writes HTML to a Python file object in chunks of `CHUNK` bytes, stopping
+/// at the first error.
+struct Writer<'py> {
+	writer: Bound<'py, PyAny>,
+	buffer: String,
+	error: Option<PyErr>,
+}
+
+impl Writer<'_> {
+	fn flush(&mut self) -> PyResult<()> {
+		if !self.buffer.is_empty() {
+			self.writer.call_method1("write", (take(&mut self.buffer),))?;
+		}
+
+		Ok(())
+	}
+}
+
+impl fmt::Write for Writer<'_> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.buffer.push_str(s);
+
+		if self.buffer.len() >= CHUNK {
+			if let Err(err) = self.flush() {
+				self.error = Some(err);
+				return Err(fmt::Error);
+			}
+		}
+
+		Ok(())
+	}
+}
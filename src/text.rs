@@ -0,0 +1,134 @@
+use crate::inputs;
+use crate::options::PyOptions;
+use ::pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use pyo3::{prelude::*, types::PyDict};
+use rayon::prelude::*;
+
+/// Collect the text of a Markdown string, dropping markup and raw HTML.
+///
+/// Soft breaks become spaces, while hard breaks and block ends become newlines.
+/// Text in front matter is always dropped, and text in code blocks is kept
+/// only if `code_blocks` is true.
+pub fn plaintext(markdown: &str, flags: Options, code_blocks: bool) -> String {
+	let mut output = String::with_capacity(markdown.len());
+	let mut skipping = false;
+
+	for event in Parser::new_ext(markdown, flags) {
+		match event {
+			Event::Start(Tag::MetadataBlock(_)) => skipping = true,
+			Event::Start(Tag::CodeBlock(_)) if !code_blocks => skipping = true,
+			Event::End(TagEnd::MetadataBlock(_) | TagEnd::CodeBlock) => {
+				skipping = false;
+				push_newline(&mut output);
+			}
+
+			Event::Text(text) | Event::Code(text) if !skipping => output.push_str(&text),
+			Event::SoftBreak => output.push(' '),
+			Event::HardBreak => output.push('\n'),
+
+			Event::End(
+				TagEnd::Emphasis
+				| TagEnd::Strong
+				| TagEnd::Strikethrough
+				| TagEnd::Superscript
+				| TagEnd::Subscript
+				| TagEnd::Link
+				| TagEnd::Image,
+			) => {}
+			Event::End(_) => push_newline(&mut output),
+
+			_ => {}
+		}
+	}
+
+	output.truncate(output.trim_end().len());
+	output
+}
+
+/// Separate blocks by a newline, without doubling those text already ends in.
+fn push_newline(output: &mut String) {
+	if !output.is_empty() && !output.ends_with('\n') {
+		output.push('\n');
+	}
+}
+
+/// The reading speed assumed by `stats`, in words per minute.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Render a list of Markdown strings into a list of plain text strings.
+///
+/// Only text and inline code are kept: tags and raw HTML are dropped, soft
+/// breaks become spaces, and hard breaks and block ends become newlines. The
+/// `math` and `code` callbacks are not called.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     An iterable of Markdown `str` or UTF-8 `bytes` to render.
+/// options
+///     The Markdown extensions to enable.
+///
+/// Returns
+/// -------
+/// A list of plain text strings which preserves the indices of `markdown`.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None))]
+pub fn to_plaintext(py: Python, markdown: &Bound<'_, PyAny>, options: Option<&PyOptions>) -> PyResult<Vec<String>> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let inputs = inputs(markdown)?;
+
+	Ok(py.allow_threads(move || {
+		inputs.par_iter()
+			.map(|buffer| plaintext(buffer, options.flags, true))
+			.collect()
+	}))
+}
+
+/// Count the words and characters of a list of Markdown strings.
+///
+/// Counts cover the same text as `to_plaintext`, except that code blocks are
+/// excluded, so listings don't inflate them.
+///
+/// Parameters
+/// ----------
+/// markdown
+///     An iterable of Markdown `str` or UTF-8 `bytes` to measure.
+/// options
+///     The Markdown extensions to enable.
+///
+/// Returns
+/// -------
+/// A list of dicts which preserves the indices of `markdown`, each with keys
+/// `words`, `characters`, and `reading_minutes`, the latter at 200 words per
+/// minute, rounded up.
+#[pyfunction]
+#[pyo3(signature = (markdown, options = None))]
+pub fn stats<'py>(
+	py: Python<'py>,
+	markdown: &Bound<'py, PyAny>,
+	options: Option<&PyOptions>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+	let default = PyOptions::default();
+	let options = options.unwrap_or(&default);
+	let inputs = inputs(markdown)?;
+
+	let counts: Vec<(usize, usize)> = py.allow_threads(move || {
+		inputs.par_iter()
+			.map(|buffer| {
+				let text = plaintext(buffer, options.flags, false);
+				(text.split_whitespace().count(), text.chars().count())
+			})
+			.collect()
+	});
+
+	counts.into_iter()
+		.map(|(words, characters)| {
+			let stats = PyDict::new(py);
+			stats.set_item("words", words)?;
+			stats.set_item("characters", characters)?;
+			stats.set_item("reading_minutes", words.div_ceil(WORDS_PER_MINUTE))?;
+			Ok(stats)
+		})
+		.collect()
+}
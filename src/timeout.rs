@@ -0,0 +1,78 @@
+use crate::error::Fatal;
+use pyo3::{
+	BoundObject,
+	prelude::*,
+	sync::GILOnceCell,
+	types::{PyDict, PyTuple},
+};
+
+/// The target of each helper thread, in Python, so that no frame of this
+/// crate is on the thread's stack if the interpreter exits while it runs,
+/// which would abort the process as the thread is unwound.
+static RUN: GILOnceCell<PyObject> = GILOnceCell::new();
+
+const RUN_SOURCE: &std::ffi::CStr = c"
+def run(put, callback, args, kwargs):
+    try:
+        put((callback(*args, **kwargs), None))
+    except BaseException as err:
+        put((None, err))
+";
+
+/// Call a user callback, failing with `Fatal::TimedOut` if it doesn't return
+/// within `timeout` seconds.
+///
+/// Python code can't be preempted, so with a timeout the callback runs on a
+/// daemon `threading.Thread`, while this one waits for its result without the
+/// GIL. A callback which times out isn't stopped, but runs on in the
+/// background, and its result is dropped; the interpreter can still exit
+/// while it runs. The callback must release the GIL to be timed, as
+/// `time.sleep`, I/O, and the interpreter's own switching between threads of
+/// Python code all do, but a C extension holding the GIL may not.
+pub fn call<'py>(
+	py: Python<'py>,
+	callback: &PyObject,
+	args: impl IntoPyObject<'py, Target = PyTuple>,
+	kwargs: &Bound<'py, PyDict>,
+	timeout: Option<f64>,
+) -> Result<PyObject, Fatal> {
+	let args = args.into_pyobject(py).map_err(Into::into)?.into_bound();
+
+	let Some(timeout) = timeout else {
+		return Ok(callback.call(py, args, Some(kwargs))?);
+	};
+
+	let run = RUN.get_or_try_init(py, || {
+		let module = PyModule::from_code(py, RUN_SOURCE, c"timeout.py", c"pulldown_cmark._timeout")?;
+		PyResult::Ok(module.getattr("run")?.unbind())
+	})?;
+
+	let queues = py.import("queue")?;
+	let queue = queues.getattr("SimpleQueue")?.call0()?;
+	let thread_kwargs = PyDict::new(py);
+	thread_kwargs.set_item("target", run)?;
+	thread_kwargs.set_item("args", (queue.getattr("put")?, callback, args, kwargs))?;
+	thread_kwargs.set_item("daemon", true)?;
+
+	let thread = py
+		.import("threading")?
+		.getattr("Thread")?
+		.call((), Some(&thread_kwargs))?;
+	thread.call_method0("start")?;
+
+	/* `get` waits without the GIL. */
+	let result = match queue.call_method1("get", (true, timeout)) {
+		Ok(result) => result,
+		Err(err) if err.is_instance(py, &queues.getattr("Empty")?) => {
+			return Err(Fatal::TimedOut { timeout });
+		}
+		Err(err) => return Err(err.into()),
+	};
+
+	let (value, err): (PyObject, Option<Bound<PyAny>>) = result.extract()?;
+
+	match err {
+		Some(err) => Err(PyErr::from_value(err).into()),
+		None => Ok(value),
+	}
+}
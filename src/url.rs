@@ -0,0 +1,125 @@
+/// Whether `url` is left unchanged by rewrites: it has a scheme (e.g.
+/// `https:`, `mailto:`, or `data:`), is protocol-relative, or is only a
+/// fragment.
+pub fn is_absolute(url: &str) -> bool {
+	url.starts_with('#') || url.starts_with("//") || scheme(url).is_some()
+}
+
+/// The lowercased host of an absolute or protocol-relative `url`, without its
+/// port or user info.
+pub fn host(url: &str) -> Option<String> {
+	let start = match scheme(url) {
+		Some(scheme) => url[scheme.len() + 1..].strip_prefix("//")?,
+		None => url.strip_prefix("//")?,
+	};
+
+	let authority = &start[..start.find(['/', '?', '#']).unwrap_or(start.len())];
+	let host = authority.rsplit('@').next().unwrap_or(authority);
+	let host = host.split(':').next().unwrap_or(host);
+
+	Some(host.to_ascii_lowercase())
+}
+
+/// Whether `url` can run code when followed: a `javascript:`, `vbscript:`, or
+/// `data:` URL. Browsers ignore whitespace and control characters in the
+/// scheme, so they are ignored here too.
+pub fn is_unsafe(url: &str) -> bool {
+	let url: String = url
+		.chars()
+		.filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+		.collect();
+
+	scheme(&url).is_some_and(|scheme| {
+		["javascript", "vbscript", "data"]
+			.iter()
+			.any(|unsafe_scheme| scheme.eq_ignore_ascii_case(unsafe_scheme))
+	})
+}
+
+/// The scheme of `url`, without its trailing colon.
+fn scheme(url: &str) -> Option<&str> {
+	let end = url.find(':')?;
+	let scheme = &url[..end];
+
+	let mut chars = scheme.chars();
+	let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+		&& chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+	valid.then_some(scheme)
+}
+
+/// Split `url` before its query or fragment, whichever comes first.
+fn split_suffix(url: &str) -> (&str, &str) {
+	url.split_at(url.find(['?', '#']).unwrap_or(url.len()))
+}
+
+/// Replace a `.md` extension on the path of a relative URL with `.html`.
+pub fn md_to_html(url: &str) -> String {
+	let (path, suffix) = split_suffix(url);
+
+	match path.strip_suffix(".md") {
+		Some(stem) => format!("{stem}.html{suffix}"),
+		None => String::from(url),
+	}
+}
+
+/// Resolve a relative URL against `base`, as a browser would.
+pub fn join(base: &str, url: &str) -> String {
+	let (base, _) = split_suffix(base);
+
+	/* Separate the `scheme://host` origin of the base, if any, from its path. */
+	let origin_end = match base.find("//") {
+		Some(start) if scheme(base).is_some() || start == 0 => {
+			base[start + 2..].find('/').map_or(base.len(), |end| start + 2 + end)
+		}
+		_ => 0,
+	};
+
+	let (origin, base_path) = base.split_at(origin_end);
+	let (path, suffix) = split_suffix(url);
+
+	let joined = if path.is_empty() {
+		String::from(base_path)
+	} else if path.starts_with('/') {
+		String::from(path)
+	} else {
+		let directory = &base_path[..base_path.rfind('/').map_or(0, |end| end + 1)];
+		format!("{directory}{path}")
+	};
+
+	/* A path directly after an origin is always absolute. */
+	let joined = match origin.is_empty() || joined.starts_with('/') {
+		true => joined,
+		false => format!("/{joined}"),
+	};
+
+	format!("{origin}{}{suffix}", remove_dot_segments(&joined))
+}
+
+/// Resolve `.` and `..` segments in a URL path.
+fn remove_dot_segments(path: &str) -> String {
+	let mut segments: Vec<&str> = Vec::new();
+	let mut parts = path.split('/').peekable();
+
+	while let Some(part) = parts.next() {
+		let last = parts.peek().is_none();
+
+		match part {
+			"." | ".." => {
+				let root = segments.len() == 1 && segments[0].is_empty();
+
+				if part == ".." && !segments.is_empty() && !root {
+					segments.pop();
+				}
+
+				/* A trailing dot segment still names a directory. */
+				if last {
+					segments.push("");
+				}
+			}
+			_ => segments.push(part),
+		}
+	}
+
+	segments.join("/")
+}
@@ -0,0 +1,200 @@
+use crate::error::BadCallbackError;
+use crate::iter::output;
+use crate::options::{PyOptions, kwargs};
+use pyo3::{
+	prelude::*,
+	types::{PyDict, PyTuple},
+};
+
+/// Call each callback of an `Options` once with a trivial input, to fail fast
+/// on a broken callback before a long batch, without rendering anything.
+///
+/// The probes are `math("x", False)`, `code("", None)`, or `code("", language)`
+/// for each language of a `code` dict, `broken_link("x")`, `wikilink("x")`, and
+/// `tasklist_marker(False)`, each passed its keywords as in `render`, with a
+/// `context`, `info`, and `alias` of `None`, empty `attrs`, and a `line` of 1.
+/// With `batch_callbacks`, the probes are `math([("x", False)])` and
+/// `code([("", None)])` instead.
+///
+/// Parameters
+/// ----------
+/// options
+///     The options whose callbacks to call.
+///
+/// Raises
+/// ------
+/// BadCallbackError
+///    If a callback raises, or returns a value `render` wouldn't accept, with
+///    the original exception as its `__cause__`.
+#[pyfunction]
+pub fn validate_options(py: Python, options: &PyOptions) -> PyResult<()> {
+	let callbacks = &options.callbacks;
+
+	if options.settings.batch_callbacks {
+		return validate_batch(py, options);
+	}
+
+	if let Some(math) = &callbacks.math {
+		probe(
+			py,
+			"`math` callback",
+			math,
+			("x", false),
+			&callbacks.math_keywords,
+			|result| match result.is_none(py) {
+				true => Ok(()),
+				false => output(py, result).map(drop),
+			},
+		)?;
+	}
+
+	match &callbacks.code_languages {
+		Some(languages) => {
+			for (language, (code, keywords)) in languages {
+				let name = format!("`code` callback for `{language}`");
+				let language = Some(language.as_str()).filter(|&language| language != "*");
+				probe(py, &name, code, ("", language), keywords, |result| {
+					output(py, result).map(drop)
+				})?;
+			}
+		}
+
+		None => {
+			if let Some(code) = &callbacks.code {
+				probe(
+					py,
+					"`code` callback",
+					code,
+					("", None::<&str>),
+					&callbacks.code_keywords,
+					|result| output(py, result).map(drop),
+				)?;
+			}
+		}
+	}
+
+	if let Some(broken_link) = &callbacks.broken_link {
+		probe(
+			py,
+			"`broken_link` callback",
+			broken_link,
+			("x",),
+			&callbacks.broken_link_keywords,
+			|result| result.extract::<Option<(String, String)>>(py).map(drop),
+		)?;
+	}
+
+	if let Some(wikilink) = &callbacks.wikilink {
+		probe(
+			py,
+			"`wikilink` callback",
+			wikilink,
+			("x",),
+			&callbacks.wikilink_keywords,
+			|result| match result.extract::<Option<String>>(py) {
+				Ok(_) => Ok(()),
+				Err(_) => result.extract::<(String, String)>(py).map(drop),
+			},
+		)?;
+	}
+
+	if let Some(tasklist_marker) = &callbacks.tasklist_marker {
+		let keywords = &callbacks.tasklist_marker_keywords;
+
+		probe(
+			py,
+			"`tasklist_marker` callback",
+			tasklist_marker,
+			(false,),
+			keywords,
+			|result| output(py, result).map(drop),
+		)?;
+	}
+
+	Ok(())
+}
+
+/// Probe the `code` and `math` callbacks of `options`, which are batched.
+fn validate_batch(py: Python, options: &PyOptions) -> PyResult<()> {
+	let callbacks = &options.callbacks;
+	let context: &[&'static str] = &["context"];
+
+	if let Some(math) = &callbacks.math {
+		let keywords = if callbacks.math_keywords.contains(&"context") {
+			context
+		} else {
+			&[]
+		};
+
+		probe(
+			py,
+			"batched `math` callback",
+			math,
+			(vec![("x", false)],),
+			keywords,
+			|result| match result.extract::<[PyObject; 1]>(py)? {
+				[result] if result.is_none(py) => Ok(()),
+				[result] => output(py, result).map(drop),
+			},
+		)?;
+	}
+
+	if let Some(code) = &callbacks.code {
+		let keywords = if callbacks.code_keywords.contains(&"context") {
+			context
+		} else {
+			&[]
+		};
+
+		probe(
+			py,
+			"batched `code` callback",
+			code,
+			(vec![("", None::<&str>)],),
+			keywords,
+			|result| {
+				let [result] = result.extract::<[PyObject; 1]>(py)?;
+				output(py, result).map(drop)
+			},
+		)?;
+	}
+
+	Ok(())
+}
+
+/// Call `callback` with `args` and the keywords it accepts, and `check` its
+/// result, raising `BadCallbackError` for the callback `name` if either fails.
+fn probe<'py>(
+	py: Python<'py>,
+	name: &str,
+	callback: &PyObject,
+	args: impl IntoPyObject<'py, Target = PyTuple>,
+	keywords: &[&'static str],
+	check: impl FnOnce(PyObject) -> PyResult<()>,
+) -> PyResult<()> {
+	let result = kwargs(py, keywords, None).and_then(|kwargs| {
+		if keywords.contains(&"attrs") {
+			kwargs.set_item("attrs", PyDict::new(py))?;
+		}
+
+		if keywords.contains(&"line") {
+			kwargs.set_item("line", 1)?;
+		}
+
+		if keywords.contains(&"info") {
+			kwargs.set_item("info", py.None())?;
+		}
+
+		if keywords.contains(&"alias") {
+			kwargs.set_item("alias", py.None())?;
+		}
+
+		callback.call(py, args, Some(&kwargs))
+	});
+
+	result.and_then(check).map_err(|err| {
+		let wrapped = BadCallbackError::new_err(format!("the {name} failed on a probe input"));
+		wrapped.set_cause(py, Some(err));
+		wrapped
+	})
+}
@@ -0,0 +1,34 @@
+"""Test the extract_links function."""
+
+# ruff: noqa: D101, D102, S101
+
+from textwrap import dedent
+
+from pulldown_cmark import Options, extract_links
+
+
+class TestExtractLinks:
+    def test_link_kinds(self) -> None:
+        markdown = dedent("""
+        [foo](https://foo.com) [bar][bar] <https://baz.com> <qux@quux.com>
+
+        ![image](https://image.com)
+
+        [bar]: https://bar.com
+        """)
+
+        assert extract_links([markdown]) == [
+            ["https://foo.com", "https://bar.com", "https://baz.com", "mailto:qux@quux.com"],
+        ]
+
+    def test_duplicates(self) -> None:
+        assert extract_links(["[foo](/a) [bar](/b) [baz](/a)", ""]) == [["/a", "/b", "/a"], []]
+
+    def test_wikilinks(self) -> None:
+        assert extract_links(["[[foo]]"]) == [[]]
+        assert extract_links(["[[foo]]"], Options(wikilinks=True)) == [["foo"]]
+
+    def test_with_offsets(self) -> None:
+        links = extract_links(["[foo](/a) <https://b.com>", "bar"], with_offsets=True)
+
+        assert links == [[("/a", 0, 9), ("https://b.com", 10, 25)], []]
@@ -0,0 +1,71 @@
+"""Test the extract_toc function."""
+
+# ruff: noqa: D101, D102, S101
+
+import re
+from textwrap import dedent
+
+from pulldown_cmark import Options, extract_toc, render
+
+
+class TestExtractToc:
+    def test_levels(self) -> None:
+        markdown = dedent("""
+        # foo
+
+        ## bar
+
+        ### baz
+        """)
+
+        assert extract_toc(markdown) == [(1, "foo", "foo"), (2, "bar", "bar"), (3, "baz", "baz")]
+
+    def test_inline_formatting(self) -> None:
+        assert extract_toc("# *foo* `bar`, baz!") == [(1, "foo bar, baz!", "foo-bar-baz")]
+
+    def test_duplicates(self) -> None:
+        assert extract_toc("# foo\n\n# foo\n\n# foo") == [
+            (1, "foo", "foo"),
+            (1, "foo", "foo-1"),
+            (1, "foo", "foo-2"),
+        ]
+
+    def test_ignores_code_and_html(self) -> None:
+        markdown = dedent("""
+        ```
+        # foo
+        ```
+
+        <h1>bar</h1>
+
+        # baz
+        """)
+
+        assert extract_toc(markdown) == [(1, "baz", "baz")]
+
+    def test_explicit_ids(self) -> None:
+        options = Options(heading_attributes=True)
+        assert extract_toc("# foo {#bar}\n\n# bar", options) == [(1, "foo", "bar"), (1, "bar", "bar-1")]
+
+    def test_heading_offset(self) -> None:
+        markdown = "# foo\n\n## bar\n\n###### baz"
+        options = Options(heading_offset=1)
+
+        assert extract_toc(markdown, options) == [(2, "foo", "foo"), (3, "bar", "bar"), (6, "baz", "baz")]
+        assert [int(level) for level in re.findall(r"<h(\d)", render(markdown, options))] == [2, 3, 6]
+
+    def test_with_offsets(self) -> None:
+        markdown = "# foo\n\nbar\n\n## ba\u00e9\n"
+
+        assert extract_toc(markdown, with_offsets=True) == [
+            (1, "foo", "foo", 0, 6),
+            (2, "ba\u00e9", "ba\u00e9", 12, 20),
+        ]
+
+    def test_matches_rendered_ids(self) -> None:
+        markdown = '# :tada: Launch\n\n## It\'s "done" :rocket:\n\n## :tada: Launch\n'
+        options = Options(emoji=True, smart_quotes_locale="de", auto_heading_ids=True)
+        toc = extract_toc(markdown, options)
+
+        assert [text for _, text, _ in toc] == ["🎉 Launch", "It’s „done“ 🚀", "🎉 Launch"]
+        assert [slug for _, _, slug in toc] == re.findall(r'id="([^"]*)"', render(markdown, options))
@@ -0,0 +1,22 @@
+"""Test the normalize function."""
+
+# ruff: noqa: D101, D102, S101
+
+from pulldown_cmark import Options, normalize, render
+
+
+class TestNormalize:
+    def test_headings(self) -> None:
+        assert normalize(["foo\n===\n\nbar\n---"]) == ["# foo\n\n## bar"]
+
+    def test_round_trip(self) -> None:
+        markdown = "foo\n===\n\n+ *bar*\n+ [baz][qux]\n\n[qux]: /quux\n\n~~~python\ncorge\n~~~\n"
+        options = Options(tables=True)
+
+        assert render(normalize([markdown], options), options) == render([markdown], options)
+
+    def test_callbacks(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        assert normalize(["```\nfoo\n```"], Options(code=callback)) == ["````\nfoo\n````"]
@@ -0,0 +1,159 @@
+"""Test the Options class."""
+
+# ruff: noqa: D101, D102, S101
+
+import pickle
+
+import pytest
+
+from pulldown_cmark import Options, PulldownCmarkError
+
+
+class TestOptions:
+    def test_repr_empty(self) -> None:
+        assert repr(Options()) == "Options()"
+
+    def test_repr_flags(self) -> None:
+        options = Options(tables=True, footnotes=True, math=lambda buffer, _: buffer)
+        assert repr(options) == "Options(tables=True, footnotes=True, math=<callback>)"
+
+    def test_repr_each_flag(self) -> None:
+        flags = [
+            "tables",
+            "footnotes",
+            "strikethrough",
+            "tasklists",
+            "smart_punctuation",
+            "heading_attributes",
+            "yaml_style_metadata_blocks",
+            "pluses_delimited_metadata_blocks",
+            "old_footnotes",
+            "gfm",
+            "definition_list",
+            "superscript",
+            "subscript",
+            "wikilinks",
+        ]
+
+        for flag in flags:
+            assert repr(Options(**{flag: True})) == f"Options({flag}=True)"  # pyright: ignore[reportArgumentType]
+
+    def test_repr_implied_flags(self) -> None:
+        assert repr(Options(footnotes=True, old_footnotes=True)) == "Options(old_footnotes=True)"
+
+    def test_extensions(self) -> None:
+        options = Options(tables=True, old_footnotes=True, math=lambda buffer, _: buffer)
+
+        assert options.extensions == ["tables", "footnotes", "old_footnotes"]
+        assert options.callbacks == ["math"]
+
+    def test_extensions_empty(self) -> None:
+        assert Options().extensions == []
+        assert Options().callbacks == []
+
+    def test_smart_quotes_locale_unknown(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="smart_quotes_locale"):
+            _ = Options(smart_quotes_locale="xx")
+
+    def test_wrapper_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="not a tag name"):
+            _ = Options(wrapper_tag="div onclick=foo")
+
+        with pytest.raises(PulldownCmarkError, match="requires `wrapper_tag`"):
+            _ = Options(wrapper_class="foo")
+
+    def test_anchor_template_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="requires `anchor_links`"):
+            _ = Options(anchor_template="#")
+
+    def test_admonition_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="unknown `admonition_style`"):
+            _ = Options(admonition_style="mkdocs")
+
+        with pytest.raises(PulldownCmarkError, match="requires `admonition_style`"):
+            _ = Options(admonition_titles={"note": "Remarque"})
+
+        with pytest.raises(PulldownCmarkError, match="unknown alert `hint`"):
+            _ = Options(admonition_style="github", admonition_titles={"hint": "Hint"})
+
+    def test_emoji_map_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="requires `emoji`"):
+            _ = Options(emoji_map={"shipit": "🐿️"})
+
+    def test_callback_timeout_invalid(self) -> None:
+        for timeout in (0, -1, float("nan"), float("inf")):
+            with pytest.raises(PulldownCmarkError, match="`callback_timeout` must be positive and finite"):
+                _ = Options(callback_timeout=timeout)
+
+    def test_footnotes_title_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="requires `collect_footnotes`"):
+            _ = Options(footnotes=True, footnotes_title="Notes")
+
+    def test_slug_style_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="unknown `slug_style` `latin`"):
+            _ = Options(slug_style="latin")  # pyright: ignore[reportArgumentType]
+
+    def test_batch_callbacks_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="requires a `code` or `math` callback"):
+            _ = Options(batch_callbacks=True)
+
+        with pytest.raises(PulldownCmarkError, match="can't batch a `code` dict"):
+            _ = Options(code={"python": lambda *_: ""}, batch_callbacks=True)
+
+    def test_disallowed_html_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="unknown `disallowed_html`"):
+            _ = Options(allowed_html_tags=["b"], disallowed_html="strip")  # pyright: ignore[reportArgumentType]
+
+        with pytest.raises(PulldownCmarkError, match="requires `allowed_html_tags`"):
+            _ = Options(disallowed_html="escape")
+
+    def test_code_dict_invalid(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="for `python` is not callable"):
+            _ = Options(code={"python": "foo"})  # pyright: ignore[reportArgumentType]
+
+    def test_eq(self) -> None:
+        assert Options(tables=True) == Options(tables=True)
+        assert hash(Options(tables=True)) == hash(Options(tables=True))
+        assert Options(tables=True) != Options(strikethrough=True)
+        assert Options(base_url="/foo/") != Options(base_url="/bar/")
+
+    def test_eq_callbacks(self) -> None:
+        def code(buffer: str, _language: str | None) -> str:
+            return buffer
+
+        assert Options(code=code) == Options(code=code)
+        assert Options(code=code) != Options(code=lambda buffer, _: buffer)
+        assert Options(code=code) != Options()
+
+    def test_pickle(self) -> None:
+        options = pickle.loads(pickle.dumps(Options(tables=True, gfm=True)))  # noqa: S301
+        assert repr(options) == "Options(tables=True, gfm=True)"
+
+    def test_pickle_callbacks(self) -> None:
+        with pytest.raises(PulldownCmarkError):
+            _ = pickle.dumps(Options(code=lambda buffer, _: buffer))
+
+    def test_katex_with_math(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="mutually exclusive"):
+            _ = Options(katex=True, math=lambda buffer, _: buffer)
+
+    def test_from_dict(self) -> None:
+        options = Options.from_dict({"tables": True, "code": lambda buffer, _: buffer})
+        assert repr(options) == "Options(tables=True, code=<callback>)"
+
+    def test_from_dict_unknown(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="foo"):
+            _ = Options.from_dict({"foo": True})
+
+    def test_gfm_extensions(self) -> None:
+        assert repr(Options.gfm_extensions()) == (
+            "Options(tables=True, footnotes=True, strikethrough=True, tasklists=True, "
+            "heading_attributes=True, gfm=True)"
+        )
+
+    def test_all_extensions(self) -> None:
+        assert repr(Options.all_extensions()) == (
+            "Options(tables=True, footnotes=True, strikethrough=True, tasklists=True, "
+            "smart_punctuation=True, heading_attributes=True, gfm=True, definition_list=True, "
+            "superscript=True, subscript=True, wikilinks=True)"
+        )
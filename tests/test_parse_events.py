@@ -0,0 +1,58 @@
+"""Test the parse_events function."""
+
+# ruff: noqa: D101, D102, S101
+
+from pulldown_cmark import Options, parse_events
+
+
+class TestParseEvents:
+    def test_text(self) -> None:
+        assert parse_events("*foo*") == [
+            {"type": "start", "tag": "paragraph"},
+            {"type": "start", "tag": "emphasis"},
+            {"type": "text", "value": "foo"},
+            {"type": "end", "tag": "emphasis"},
+            {"type": "end", "tag": "paragraph"},
+        ]
+
+    def test_heading(self) -> None:
+        events = parse_events("## foo {#bar .baz}", Options(heading_attributes=True))
+
+        assert events[0] == {
+            "type": "start",
+            "tag": "heading",
+            "level": 2,
+            "id": "bar",
+            "classes": ["baz"],
+            "attrs": [],
+        }
+
+    def test_link(self) -> None:
+        events = parse_events('[foo](/bar "baz")')
+
+        assert events[1] == {
+            "type": "start",
+            "tag": "link",
+            "link_type": "inline",
+            "dest_url": "/bar",
+            "title": "baz",
+            "id": "",
+        }
+
+    def test_extensions(self) -> None:
+        options = Options(tasklists=True, footnotes=True, math=lambda buffer, _: buffer)
+        events = parse_events("- [x] $a$ $$b$$[^c]\n\n[^c]: d\n", options)
+
+        assert {"type": "task_list_marker", "checked": True} in events
+        assert {"type": "inline_math", "value": "a"} in events
+        assert {"type": "display_math", "value": "b"} in events
+        assert {"type": "footnote_reference", "value": "c"} in events
+        assert {"type": "start", "tag": "footnote_definition", "label": "c"} in events
+
+    def test_code_block(self) -> None:
+        assert parse_events("```rust\nfoo\n```")[0] == {
+            "type": "start",
+            "tag": "code_block",
+            "fenced": True,
+            "info": "rust",
+        }
@@ -0,0 +1,61 @@
+"""Test the parse_frontmatter function."""
+
+# ruff: noqa: D101, D102, S101
+
+import pytest
+
+from pulldown_cmark import BadFrontmatterError, PulldownCmarkError, parse_frontmatter
+
+
+class TestParseFrontmatter:
+    def test_yaml(self) -> None:
+        markdown = "---\ntitle: foo\ntags: [a, b]\ndraft: false\n---\n# bar\n"
+        assert parse_frontmatter(markdown) == (
+            {"title": "foo", "tags": ["a", "b"], "draft": False},
+            "# bar\n",
+        )
+
+    def test_yaml_values(self) -> None:
+        markdown = "---\na: 1\nb: 18446744073709551615\nc: 1.5\nd: ~\ne: !!str 3\nf: &x [1, {g: h}]\ni: *x\n---\n"
+        assert parse_frontmatter(markdown) == (
+            {
+                "a": 1,
+                "b": 18446744073709551615,
+                "c": 1.5,
+                "d": None,
+                "e": "3",
+                "f": [1, {"g": "h"}],
+                "i": [1, {"g": "h"}],
+            },
+            "",
+        )
+
+    def test_toml(self) -> None:
+        markdown = '+++\ntitle = "foo"\ndate = 2024-01-02\n\n[extra]\nweight = 1.5\n+++\n# bar\n'
+        assert parse_frontmatter(markdown) == (
+            {"title": "foo", "date": "2024-01-02", "extra": {"weight": 1.5}},
+            "# bar\n",
+        )
+
+    def test_empty(self) -> None:
+        assert parse_frontmatter("---\n---\n# bar\n") == ({}, "# bar\n")
+
+    def test_absent(self) -> None:
+        assert parse_frontmatter("# foo\n") == (None, "# foo\n")
+
+    def test_malformed_yaml(self) -> None:
+        with pytest.raises(BadFrontmatterError, match="cannot parse YAML front matter"):
+            _ = parse_frontmatter("---\ntitle: [foo\n---\n")
+
+    def test_malformed_toml(self) -> None:
+        with pytest.raises(BadFrontmatterError, match="cannot parse TOML front matter"):
+            _ = parse_frontmatter("+++\ntitle =\n+++\n")
+
+    def test_not_a_mapping(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="not a mapping"):
+            _ = parse_frontmatter("---\n- foo\n---\n")
+
+    def test_complex_key(self) -> None:
+        for markdown in ("---\n[a, b]: 1\n---\n", "---\nc:\n  ? [a, b]\n  : 1\n---\n"):
+            with pytest.raises(BadFrontmatterError, match="a mapping key is not a scalar"):
+                _ = parse_frontmatter(markdown)
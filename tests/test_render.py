@@ -2,9 +2,14 @@
 
 # ruff: noqa: D101, D102, S101
 
+import re
+import subprocess
+import sys
+import time
 from textwrap import dedent
-from typing import cast
+from typing import Literal, cast
 
+import pytest
 from bs4 import BeautifulSoup
 from bs4.element import NavigableString
 from latex2mathml.converter import convert as to_mathml
@@ -12,7 +17,15 @@ from pygments import highlight  # pyright: ignore[reportUnknownVariableType]
 from pygments.formatters import HtmlFormatter
 from pygments.lexers import get_lexer_by_name, guess_lexer
 
-from pulldown_cmark import Options, render
+from pulldown_cmark import (
+    BadCallbackError,
+    BadTableError,
+    CallbackTimeoutError,
+    CannotRenderMathError,
+    Options,
+    PulldownCmarkError,
+    render,
+)
 
 
 class TestRender:
@@ -385,6 +398,43 @@ class TestRender:
 
         TestRender.assert_render(html, markdown, Options(wikilinks=True))
 
+    def test_auto_heading_ids(self) -> None:
+        html = """
+        <h1 id="foo">
+          foo
+        </h1>
+        <h1 id="foo-1">
+          foo
+        </h1>
+        <h2 id="foo-bar-baz">
+          foo
+          <code>
+            bar
+          </code>
+          baz!
+        </h2>
+        """
+
+        markdown = """
+        # foo
+
+        # foo
+
+        ## foo `bar` baz!
+        """
+
+        TestRender.assert_render(html, markdown, Options(auto_heading_ids=True))
+
+    def test_slug_style(self) -> None:
+        markdown = "# Café Münü\n\n# Привет мир\n\n# 中文标题\n"
+
+        def ids(slug_style: Literal["ascii", "github"] | None) -> list[str]:
+            html = render(markdown, Options(auto_heading_ids=True, slug_style=slug_style))
+            return re.findall(r'id="([^"]*)"', html)
+
+        assert ids(None) == ids("github") == ["café-münü", "привет-мир", "中文标题"]
+        assert ids("ascii") == ["cafe-munu", "privet-mir", "zhong-wen-biao-ti"]
+
     def test_math_inline(self) -> None:
         html = r"""
         <p>
@@ -504,3 +554,900 @@ class TestRender:
         """
 
         TestRender.assert_render(html, markdown, Options(code=TestRender.code_callback))
+
+    def test_code_indented(self) -> None:
+        calls: list[tuple[str, str | None]] = []
+
+        def callback(buffer: str, language: str | None, /) -> str:
+            calls.append((buffer, language))
+            return "<pre>foo</pre>"
+
+        markdown = dedent("""
+        foo
+
+            let x
+                = 1;
+        """)
+
+        html = render([markdown], Options(code=callback))[0]
+
+        assert calls == [("let x\n    = 1;\n", None)]
+        assert html == "<p>foo</p>\n<pre>foo</pre>"
+
+    def test_code_language(self) -> None:
+        calls: list[tuple[str, str | None]] = []
+
+        def callback(buffer: str, language: str | None, /) -> str:
+            calls.append((buffer, language))
+            return ""
+
+        markdown = dedent("""
+        ```rust
+        foo
+        ```
+
+        ```
+        bar
+        ```
+        """)
+
+        _ = render([markdown], Options(code=callback))
+
+        assert calls == [("foo\n", "rust"), ("bar\n", None)]
+
+    def test_iterable(self) -> None:
+        expected = ["<p>foo</p>\n", "<p>bar</p>\n"]
+
+        assert render(("foo", "bar")) == expected
+        assert render(text for text in ["foo", "bar"]) == expected
+
+    def test_iterable_not_string(self) -> None:
+        with pytest.raises(TypeError, match="input 1 is not a string"):
+            _ = render(["foo", 1])  # pyright: ignore[reportArgumentType]
+
+        with pytest.raises(TypeError, match="not iterable"):
+            _ = render(1)  # pyright: ignore[reportCallIssue, reportArgumentType]
+
+    def test_single(self) -> None:
+        assert render("*foo*") == "<p><em>foo</em></p>\n"
+        assert render(b"*foo*") == "<p><em>foo</em></p>\n"
+        assert render("", threads=1) == ""
+
+    def test_bytes(self) -> None:
+        assert render([b"*foo*", "bar"]) == ["<p><em>foo</em></p>\n", "<p>bar</p>\n"]
+
+    def test_bytes_invalid(self) -> None:
+        with pytest.raises(ValueError, match="input 1 is not valid UTF-8") as info:
+            _ = render(["foo", b"\xff"])
+
+        assert isinstance(info.value.__cause__, UnicodeDecodeError)
+
+    def test_threads(self) -> None:
+        markdown = [f"*{index}*" for index in range(100)]
+
+        assert render(markdown, threads=1) == render(markdown)
+
+    def test_threads_callback_batch(self) -> None:
+        def code(buffer: str, language: str | None) -> str:
+            return f"<pre>{language}: {buffer}</pre>"
+
+        markdown = [f"# {index}\n\n```py\n{index}\n```\n" for index in range(10_000)]
+        html = render(markdown, Options(code=code), threads=4)
+
+        assert html[1234] == "<h1>1234</h1>\n<pre>py: 1234\n</pre>"
+        assert len(html) == len(markdown)
+
+    def test_threads_zero(self) -> None:
+        with pytest.raises(PulldownCmarkError, match="must be positive"):
+            _ = render(["foo"], threads=0)
+
+    def test_code_attrs(self) -> None:
+        calls: list[tuple[str | None, dict[str, str]]] = []
+
+        def callback(buffer: str, language: str | None, /, attrs: dict[str, str]) -> str:
+            calls.append((language, attrs))
+            return buffer
+
+        markdown = dedent("""
+        ```python {.numberLines startFrom="5" hl_lines="2 4"}
+        foo
+        ```
+
+        ```rust
+        bar
+        ```
+        """)
+
+        _ = render([markdown], Options(code=callback))
+
+        assert calls == [
+            ("python", {"class": "numberLines", "startFrom": "5", "hl_lines": "2 4"}),
+            ("rust", {}),
+        ]
+
+    def test_code_line(self) -> None:
+        lines: list[int] = []
+
+        def callback(buffer: str, _language: str | None, /, line: int) -> str:
+            lines.append(line)
+            return buffer
+
+        markdown = "# foo\n\n```python\nbar\n```\n\n> ```\n> baz\n> ```\n\n    qux\n\n```\n```\n"
+        _ = render([markdown], Options(code=callback))
+
+        assert lines == [4, 8, 11, 14]
+
+    def test_code_info(self) -> None:
+        calls: list[tuple[str | None, str | None]] = []
+
+        def callback(buffer: str, language: str | None, /, info: str | None) -> str:
+            calls.append((language, info))
+            return buffer
+
+        markdown = '```python title="app.py"  {.numberLines}\nfoo\n```\n\n```rust,ignore\nbaz\n```\n\n    bar\n'
+        html = render([markdown], Options(code=callback))[0]
+
+        assert calls == [
+            ("python", 'python title="app.py"  {.numberLines}'),
+            ("rust", "rust,ignore"),
+            (None, None),
+        ]
+        assert html == "foo\nbaz\nbar\n"
+
+    def test_context(self) -> None:
+        def code(buffer: str, _language: str | None, /, context: str | None) -> str:
+            return f"{context}: {buffer}"
+
+        def broken_link(reference: str, /, **kwargs: object) -> tuple[str, str]:
+            return (f"/{kwargs['context']}/{reference}", "")
+
+        options = Options(code=code, broken_link=broken_link)
+        html = render(["```\nfoo\n```\n[bar]", "```\nbaz\n```"], options, context=["a", "b"])
+
+        assert html == ['a: foo\n<p><a href="/a/bar">bar</a></p>\n', "b: baz\n"]
+
+    def test_context_single(self) -> None:
+        def code(buffer: str, _language: str | None, /, context: str | None) -> str:
+            return f"{context}: {buffer}"
+
+        assert render("```\nfoo\n```", Options(code=code), context="a") == "a: foo\n"
+
+    def test_context_length(self) -> None:
+        with pytest.raises(ValueError, match="one item per input"):
+            _ = render(["foo", "bar"], context=["baz"])
+
+    def test_callback_text(self) -> None:
+        def callback(buffer: str, language: str | None, /) -> tuple[str, bool]:
+            return (buffer, language == "html")
+
+        markdown = ["```\n<b>\n```", "```html\n<i>\n```"]
+
+        assert render(markdown, Options(code=callback)) == ["&lt;b&gt;\n", "<i>\n"]
+
+    def test_callback_error_index(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        markdown = ["foo", "bar", "```\nbaz\n```"]
+
+        with pytest.raises(BadCallbackError, match="while rendering input 2"):
+            _ = render(markdown, Options(code=callback))
+
+    def test_bad_callback_cause(self) -> None:
+        error = ValueError("foo")
+
+        def code(_buffer: str, _language: str | None) -> str:
+            raise error
+
+        with pytest.raises(BadCallbackError) as info:
+            _ = render(["    bar"], Options(code=code))
+
+        assert info.value.__cause__ is error
+        assert info.value.__cause__.__traceback__ is not None
+
+    def test_code_block_class(self) -> None:
+        options = Options(code_block_class="highlight")
+        html = render("```rust\nfn main() {}\n```\n\n```\nfoo\n```\n\n    bar\n", options)
+
+        assert html == (
+            '<pre><code class="highlight language-rust">fn main() {}\n</code></pre>\n'
+            '<pre><code class="highlight">foo\n</code></pre>\n'
+            "<pre><code>bar\n</code></pre>\n"
+        )
+
+    def test_code_block_class_callback(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            return f"<div>{buffer}</div>"
+
+        markdown = "```python\nfoo\n```\n\n```rust\nbar\n```\n"
+
+        assert render(markdown, Options(code=callback, code_block_class="highlight")) == (
+            "<div>foo\n</div><div>bar\n</div>"
+        )
+        assert render(markdown, Options(code={"python": callback}, code_block_class="highlight")) == (
+            '<div>foo\n</div>\n<pre><code class="highlight language-rust">bar\n</code></pre>\n'
+        )
+
+    def test_batch_callbacks(self) -> None:
+        calls: list[list[tuple[str, str | None]]] = []
+
+        def code(items: list[tuple[str, str | None]], /, context: str) -> list[str]:
+            calls.append(items)
+            return [f"<pre {context}={language}>{buffer}</pre>" for buffer, language in items]
+
+        markdown = "```python\nfoo\n```\n\nbar\n\n```\nbaz\n```\n"
+
+        assert render([markdown], Options(code=code, batch_callbacks=True), context=["x"]) == [
+            "<pre x=python>foo\n</pre>\n<p>bar</p>\n<pre x=None>baz\n</pre>"
+        ]
+        assert calls == [[("foo\n", "python"), ("baz\n", None)]]
+
+    def test_batch_callbacks_math(self) -> None:
+        def math(items: list[tuple[str, bool]], /) -> list[str | None]:
+            return [None if buffer == "y" else f"<m{display:d}>{buffer}</m>" for buffer, display in items]
+
+        def single(buffer: str, display: bool, /) -> str | None:
+            return None if buffer == "y" else f"<m{display:d}>{buffer}</m>"
+
+        markdown = "# $x$\n\n$y$ and $$z$$ [^1]\n\n[^1]: $w$\n"
+        features = {"footnotes": True, "collect_footnotes": True, "auto_heading_ids": True}
+
+        assert render(markdown, Options(math=math, batch_callbacks=True, **features)) == render(
+            markdown, Options(math=single, **features)
+        )
+
+    def test_batch_callbacks_count(self) -> None:
+        options = Options(code=lambda items: items[1:], batch_callbacks=True)  # pyright: ignore[reportArgumentType]
+
+        with pytest.raises(BadCallbackError) as info:
+            _ = render("```\nfoo\n```", options)
+
+        assert isinstance(info.value.__cause__, ValueError)
+        assert str(info.value.__cause__) == "the batched `code` callback returned 0 results for 1 calls"
+
+    def test_callback_timeout(self) -> None:
+        def code(buffer: str, _language: str | None, /) -> str:
+            time.sleep(float(buffer))
+            return buffer
+
+        options = Options(code=code, callback_timeout=0.5)
+
+        assert render("```\n0\n```", options) == "0\n"
+
+        start = time.monotonic()
+
+        with pytest.raises(CallbackTimeoutError, match="exceeded `callback_timeout` of 0.5 seconds"):
+            _ = render(["```\n0\n```", "```\n5\n```"], options)
+
+        assert time.monotonic() - start < 5
+
+    def test_callback_timeout_spinning(self) -> None:
+        script = dedent("""
+            from pulldown_cmark import CallbackTimeoutError, Options, render
+
+            def code(buffer, language, /):
+                while True:
+                    pass
+
+            try:
+                render("```\\nfoo\\n```", Options(code=code, callback_timeout=0.2))
+            except CallbackTimeoutError:
+                print("timed out")
+        """)
+        result = subprocess.run(  # noqa: S603
+            [sys.executable, "-c", script], capture_output=True, text=True, timeout=30, check=False
+        )
+
+        assert (result.returncode, result.stdout) == (0, "timed out\n")
+
+    def test_callback_timeout_broken_link(self) -> None:
+        def broken_link(reference: str, /) -> tuple[str, str]:
+            time.sleep(5)
+            return (reference, "")
+
+        options = Options(broken_link=broken_link, callback_timeout=0.1)
+
+        with pytest.raises(BadCallbackError, match="`callback_timeout`"):
+            _ = render("[foo]", options)
+
+    def test_math_none(self) -> None:
+        def math(buffer: str, display: bool) -> str | None:  # noqa: FBT001
+            return f"<div>{buffer}</div>" if display else None
+
+        html = render(["$x$\n\n$$y$$"], Options(math=math))[0]
+
+        assert html == '<p><span class="math math-inline">x</span></p>\n<p><div>y</div></p>\n'
+
+    def test_cannot_render_math(self) -> None:
+        def callback(buffer: str, _display: bool, /) -> str:  # noqa: FBT001
+            raise CannotRenderMathError(buffer)
+
+        message = r"cannot render math `\\frac\{1\}` while rendering input 0"
+
+        with pytest.raises(CannotRenderMathError, match=message) as info:
+            _ = render(["$\\frac{1}$"], Options(math=callback))
+
+        assert isinstance(info.value.__cause__, CannotRenderMathError)
+        assert info.value.__cause__.args == ("\\frac{1}",)
+
+    def test_katex_error(self) -> None:
+        _ = pytest.importorskip("katex")
+
+        with pytest.raises(CannotRenderMathError, match="cannot render math") as info:
+            _ = render(["$\\frac{1}$"], Options(katex=True))
+
+        assert info.value.__cause__ is not None
+
+    def test_katex(self) -> None:
+        katex = pytest.importorskip("katex")
+
+        html = render(["$x$"], Options(katex=True))[0]
+
+        assert html == f"<p>{katex.render('x', display_mode=False)}</p>\n"
+
+    def test_broken_link(self) -> None:
+        def callback(reference: str, /) -> tuple[str, str] | None:
+            return ("/foo", "bar") if reference == "baz" else None
+
+        html = """
+        <p>
+          <a href="/foo" title="bar">
+            qux
+          </a>
+          [quux][corge]
+        </p>
+        """
+
+        markdown = """
+        [qux][baz] [quux][corge]
+        """
+
+        TestRender.assert_render(html, markdown, Options(broken_link=callback))
+
+    def test_wikilink(self) -> None:
+        def callback(page: str, /) -> str | tuple[str, str] | None:
+            return {"Foo Bar": ("/wiki/foo-bar", "Foo"), "Baz": "/wiki/baz"}.get(page)
+
+        options = Options(wikilinks=True, wikilink=callback)
+        html = render(["[[Foo Bar]] [[Baz|qux]] [[Quux]] [Baz](Baz)"], options)[0]
+
+        assert html == (
+            '<p><a href="/wiki/foo-bar" title="Foo">Foo Bar</a>'
+            ' <a href="/wiki/baz">qux</a>'
+            ' <a href="Quux">Quux</a>'
+            ' <a href="Baz">Baz</a></p>\n'
+        )
+
+    def test_wikilink_alias(self) -> None:
+        calls: list[tuple[str, str | None]] = []
+
+        def callback(page: str, /, alias: str | None) -> str:
+            calls.append((page, alias))
+            return f"/wiki/{page.lower()}"
+
+        options = Options(wikilinks=True, wikilink=callback)
+        html = render("[[Foo]] [[Bar|the *bar* `page`]]", options)
+
+        assert html == (
+            '<p><a href="/wiki/foo">Foo</a>'
+            ' <a href="/wiki/bar">the <em>bar</em> <code>page</code></a></p>\n'
+        )
+        assert calls == [("Foo", None), ("Bar", "the bar page")]
+
+    def test_base_url(self) -> None:
+        html = """
+        <p>
+          <a href="https://foo.com/bar/baz.md">
+            a
+          </a>
+          <a href="https://foo.com/qux">
+            b
+          </a>
+          <a href="#quux">
+            c
+          </a>
+          <a href="//corge.com/">
+            d
+          </a>
+          <a href="https://grault.com/">
+            e
+          </a>
+          <a href="mailto:garply@waldo.com">
+            f
+          </a>
+          <img alt="g" src="https://foo.com/bar/fred.png">
+        </p>
+        """
+
+        markdown = """
+        [a](./baz.md) [b](../qux) [c](#quux) [d](//corge.com/) [e](https://grault.com/)
+        [f](mailto:garply@waldo.com) ![g](fred.png)
+        """
+
+        TestRender.assert_render(html, markdown, Options(base_url="https://foo.com/bar/"))
+
+    def test_rewrite_md_links(self) -> None:
+        html = """
+        <p>
+          <a href="foo.html#bar">
+            a
+          </a>
+          <a href="https://baz.com/qux.md">
+            b
+          </a>
+        </p>
+        """
+
+        markdown = """
+        [a](foo.md#bar) [b](https://baz.com/qux.md)
+        """
+
+        TestRender.assert_render(html, markdown, Options(rewrite_md_links=True))
+
+    def test_sanitize(self) -> None:
+        markdown = dedent("""
+        <script>alert(1)</script>
+
+        foo <b onclick="alert(1)">bar</b> [baz](javascript:alert(1)) ![qux](data:text/html,quux)
+        """)
+
+        html = render([markdown], Options(sanitize=True))[0]
+
+        assert html == '<p>foo bar <a href="">baz</a> <img src="" alt="qux" /></p>\n'
+
+    def test_nofollow_external(self) -> None:
+        html = """
+        <p>
+          <a href="https://foo.com/bar">
+            a
+          </a>
+          <a href="./baz">
+            b
+          </a>
+          <a href="https://qux.com/" rel="nofollow noopener" target="_blank">
+            c
+          </a>
+        </p>
+        """
+
+        markdown = """
+        [a](https://foo.com/bar) [b](./baz) [c](https://qux.com/)
+        """
+
+        options = Options(nofollow_external=True, base_host="foo.com")
+        TestRender.assert_render(html, markdown, options)
+
+    def test_heading_offset(self) -> None:
+        options = Options(heading_offset=1, heading_attributes=True)
+        html = render(["# foo {#bar .baz}\n\n###### qux"], options)[0]
+
+        assert html == '<h2 id="bar" class="baz">foo</h2>\n<h6>qux</h6>\n'
+
+    def test_heading_offset_negative(self) -> None:
+        html = render(["# foo\n\n### bar"], Options(heading_offset=-2))[0]
+
+        assert html == "<h1>foo</h1>\n<h1>bar</h1>\n"
+
+    def test_tasklist_marker(self) -> None:
+        def callback(checked: bool, /) -> str:  # noqa: FBT001
+            return "☑ " if checked else "☐ "
+
+        options = Options(tasklists=True, tasklist_marker=callback)
+        html = render(["- [x] foo\n- [ ] bar\n- baz"], options)[0]
+
+        assert html == "<ul>\n<li>☑ foo</li>\n<li>☐ bar</li>\n<li>baz</li>\n</ul>\n"
+
+    def test_strip_comments(self) -> None:
+        markdown = "<!-- foo -->\n\nbar <!-- baz --> `<!-- qux -->`\n"
+        html = render([markdown], Options(strip_comments=True))[0]
+
+        assert html == "<p>bar  <code>&lt;!-- qux --&gt;</code></p>\n"
+
+    def test_strip_comments_in_html(self) -> None:
+        markdown = "<div>\n<!-- foo\nbar -->\n<p>baz</p><!-- qux -->\n</div>\n"
+        html = render([markdown], Options(strip_comments=True))[0]
+
+        assert html == "<div>\n<p>baz</p>\n</div>\n"
+
+    def test_hardbreaks(self) -> None:
+        markdown = "foo\nbar\n\n```\nbaz\nqux\n```\n"
+        html = render([markdown], Options(hardbreaks=True))[0]
+
+        assert html == "<p>foo<br />\nbar</p>\n<pre><code>baz\nqux\n</code></pre>\n"
+        assert render(["foo\nbar"])[0] == "<p>foo\nbar</p>\n"
+
+    def test_lazy_images(self) -> None:
+        markdown = '![*foo* `bar`](baz.png "qux") ![](https://quux.com/a.jpg) ![b](data:c)'
+        html = render([markdown], Options(lazy_images=True))[0]
+
+        assert html == (
+            '<p><img src="baz.png" alt="foo bar" title="qux" loading="lazy" />'
+            ' <img src="https://quux.com/a.jpg" alt="" loading="lazy" />'
+            ' <img src="data:c" alt="b" loading="lazy" /></p>\n'
+        )
+
+    def test_lazy_images_nested(self) -> None:
+        markdown = "![a ![c](d)](u)"
+
+        assert render(markdown, Options(lazy_images=True)) == '<p><img src="u" alt="a c" loading="lazy" /></p>\n'
+        assert render(markdown) == '<p><img src="u" alt="a c" /></p>\n'
+
+    def test_smart_quotes_locale(self) -> None:
+        markdown = "\"foo\" 'bar' it's *\"baz\"* `\"qux\"`"
+
+        for locale, html in [
+            ("en", "“foo” ‘bar’ it’s <em>“baz”</em>"),
+            ("fr", "«foo» ‹bar› it’s <em>«baz»</em>"),
+            ("de", "„foo“ ‚bar‘ it’s <em>„baz“</em>"),
+        ]:
+            options = Options(smart_punctuation=True, smart_quotes_locale=locale)
+            expected = f'<p>{html} <code>"qux"</code></p>\n'
+
+            assert render([markdown], options)[0] == expected
+
+    def test_smart_quotes_locale_straight(self) -> None:
+        html = render(["\"foo\" 'bar'"], Options(smart_quotes_locale="de-CH"))[0]
+
+        assert html == "<p>„foo“ ‚bar‘</p>\n"
+
+    def test_escape_html(self) -> None:
+        markdown = "<div>\nfoo\n</div>\n\n<b>hi</b> bar\n"
+        html = render([markdown], Options(escape_html=True, sanitize=True))[0]
+
+        assert html == "&lt;div&gt;\nfoo\n&lt;/div&gt;\n<p>&lt;b&gt;hi&lt;/b&gt; bar</p>\n"
+
+    def test_max_input_bytes(self) -> None:
+        options = Options(max_input_bytes=3)
+
+        assert render(["foo"], options) == ["<p>foo</p>\n"]
+
+        with pytest.raises(ValueError, match="4 bytes .* while rendering input 1"):
+            _ = render(["foo", "barr"], options)
+
+    def test_max_nesting_depth(self) -> None:
+        options = Options(max_nesting_depth=4)
+
+        assert render(["> - *foo*"], options) == [
+            "<blockquote>\n<ul>\n<li><em>foo</em></li>\n</ul>\n</blockquote>\n",
+        ]
+
+        with pytest.raises(ValueError, match="`max_nesting_depth` of 4"):
+            _ = render(["> > - *foo*"], options)
+
+    def test_wrapper(self) -> None:
+        options = Options(wrapper_tag="article", wrapper_class='markdown-body "foo"')
+        html = render(["# bar"], options)[0]
+
+        assert html == (
+            '<article class="markdown-body &quot;foo&quot;">\n<h1>bar</h1>\n</article>\n'
+        )
+        assert render(["bar"], Options(wrapper_tag="div"))[0] == "<div>\n<p>bar</p>\n</div>\n"
+
+    def test_anchor_links(self) -> None:
+        markdown = "# foo\n\n## bar {#custom}\n\n# foo\n"
+        options = Options(
+            auto_heading_ids=True,
+            anchor_links=True,
+            heading_attributes=True,
+            heading_offset=1,
+        )
+        html = render([markdown], options)[0]
+
+        assert html == (
+            '<h2 id="foo"><a class="anchor" aria-hidden="true" href="#foo"></a>foo</h2>\n'
+            '<h3 id="custom"><a class="anchor" aria-hidden="true" href="#custom"></a>bar</h3>\n'
+            '<h2 id="foo-1"><a class="anchor" aria-hidden="true" href="#foo-1"></a>foo</h2>\n'
+        )
+
+    def test_anchor_template(self) -> None:
+        options = Options(
+            anchor_links=True,
+            anchor_template='<a href="#{slug}">#</a> ',
+            heading_attributes=True,
+        )
+        html = render(["# foo {#a&b}\n\n# bar"], options)[0]
+
+        assert html == '<h1 id="a&amp;b"><a href="#a&amp;b">#</a> foo</h1>\n<h1>bar</h1>\n'
+
+    def test_definition_classes(self) -> None:
+        markdown = "foo\n: bar\n\n  baz\n  : qux\n"
+        options = Options(
+            definition_list=True, dl_class="list", dt_class="term", dd_class="def"
+        )
+        html = render([markdown], options)[0]
+
+        assert html == (
+            '<dl class="list">\n<dt class="term">foo</dt>\n<dd class="def">\n<p>bar</p>\n'
+            '<dl class="list">\n<dt class="term">baz</dt>\n<dd class="def">qux</dd>\n</dl>\n'
+            "</dd>\n</dl>\n"
+        )
+
+    def test_definition_classes_nested(self) -> None:
+        markdown = "foo\n: bar\n  : baz\n"
+        html = render([markdown], Options(definition_list=True, dl_class="list"))[0]
+        expected = render([markdown], Options(definition_list=True))[0]
+
+        assert html == expected.replace("<dl>", '<dl class="list">')
+
+    def test_admonition_style(self) -> None:
+        markdown = "> [!NOTE]\n> foo\n\n> bar\n"
+        html = render([markdown], Options(gfm=True, admonition_style="admonition"))[0]
+
+        assert html == (
+            '<div class="admonition note">\n<p class="admonition-title">Note</p>\n'
+            "<p>foo</p>\n</div>\n<blockquote>\n<p>bar</p>\n</blockquote>\n"
+        )
+
+    def test_admonition_style_github(self) -> None:
+        options = Options(
+            gfm=True,
+            admonition_style="github",
+            admonition_titles={"warning": "Achtung & co"},
+        )
+        html = render(["> [!WARNING]\n> foo\n"], options)[0]
+
+        assert html == (
+            '<div class="markdown-alert markdown-alert-warning">\n'
+            '<p class="markdown-alert-title">Achtung &amp; co</p>\n<p>foo</p>\n</div>\n'
+        )
+
+    def test_max_blocks(self) -> None:
+        markdown = "# foo\n\n> bar\n> * baz\n\n---\n\nqux\n"
+
+        assert render([markdown], Options(max_blocks=0))[0] == ""
+        assert render([markdown], Options(max_blocks=2))[0] == (
+            "<h1>foo</h1>\n<blockquote>\n<p>bar</p>\n<ul>\n<li>baz</li>\n</ul>\n</blockquote>\n"
+        )
+        assert render([markdown], Options(max_blocks=3))[0].endswith("<hr />\n")
+        assert render([markdown], Options(max_blocks=9)) == render([markdown])
+
+    def test_autolink(self) -> None:
+        markdown = "(see https://example.com/a_b?c=d), or www.example.com.\n"
+        html = render([markdown], Options(autolink=True))[0]
+
+        assert html == (
+            '<p>(see <a href="https://example.com/a_b?c=d">https://example.com/a_b?c=d</a>),'
+            ' or <a href="http://www.example.com">www.example.com</a>.</p>\n'
+        )
+
+    def test_autolink_in_links(self) -> None:
+        markdown = "[https://foo.com](https://bar.com) <https://baz.com> `https://qux.com`\n"
+        html = render([markdown], Options(autolink=True))[0]
+
+        assert html == render([markdown])[0]
+
+    def test_strict_tables(self) -> None:
+        options = Options(tables=True, strict_tables=True)
+
+        with pytest.raises(BadTableError, match="row 2 has 2 cells, but the header has 3"):
+            _ = render(["| a | b | c |\n|---|---|---|\n| 1 | 2 | 3 |\n| 4 | 5 |\n"], options)
+
+        with pytest.raises(BadTableError, match="row 1 has 4 cells, but the header has 3"):
+            _ = render(["| a | b | c |\n|---|---|---|\n| 1 | 2 | 3 | 4 |\n"], options)
+
+    def test_strict_tables_empty_cells(self) -> None:
+        markdown = "| a | b | c |\n|---|---|---|\n| 1 | | \\| |\n"
+        html = render([markdown], Options(tables=True, strict_tables=True))[0]
+
+        assert html == render([markdown], Options(tables=True))[0]
+
+    def test_callback_list(self) -> None:
+        def code(buffer: str, language: str | None) -> list[str | tuple[str, bool]]:
+            return [f"<figure><pre>{buffer}</pre>", (f"<{language}>", False), "</figure>\n"]
+
+        html = render(["```foo\nbar\n```\n\nbaz"], Options(code=code))[0]
+
+        assert html == "<figure><pre>bar\n</pre>&lt;foo&gt;</figure>\n<p>baz</p>\n"
+
+    def test_code_dict(self) -> None:
+        markdown = "```mermaid\nfoo\n```\n\n```python\nbar\n```\n"
+        options = Options(code={"mermaid": lambda buffer, _: f"<div>{buffer}</div>\n"})
+        html = render([markdown], options)[0]
+
+        assert html == (
+            '<div>foo\n</div>\n<pre><code class="language-python">bar\n</code></pre>\n'
+        )
+
+    def test_code_dict_info_words(self) -> None:
+        markdown = '```python title="app.py"\nfoo\n```\n\n```rust,ignore\nbar\n```\n'
+        options = Options(code={"python": lambda buffer, _: f"<py>{buffer}</py>", "rust": lambda buffer, _: buffer})
+        html = render([markdown], options)[0]
+
+        assert html == "<py>foo\n</py>bar\n"
+
+    def test_code_dict_default(self) -> None:
+        def default(buffer: str, language: str | None, attrs: dict[str, str]) -> str:
+            return f"<pre>{language} {attrs}: {buffer}</pre>\n"
+
+        markdown = "```mermaid\nfoo\n```\n\n```python {.x}\nbar\n```\n"
+        options = Options(code={"mermaid": lambda *_: "", "*": default})
+        html = render([markdown], options)[0]
+
+        assert html == "<pre>python {'class': 'x'}: bar\n</pre>\n"
+
+    def test_collect_footnotes(self) -> None:
+        markdown = "foo[^1] bar[^2] baz[^1]\n\n[^2]: qux\n[^1]: quux\n\n        code\n"
+        options = Options(footnotes=True, collect_footnotes=True)
+        html = render([markdown], options)[0]
+
+        assert html == (
+            '<p>foo<sup class="footnote-ref"><a href="#fn-1" id="fnref-1">1</a></sup>'
+            ' bar<sup class="footnote-ref"><a href="#fn-2" id="fnref-2">2</a></sup>'
+            ' baz<sup class="footnote-ref"><a href="#fn-1" id="fnref-1-2">1</a></sup></p>\n'
+            '<section class="footnotes" data-footnotes>\n'
+            '<h2 id="footnote-label" class="sr-only">Footnotes</h2>\n'
+            "<ol>\n"
+            '<li id="fn-1">\n'
+            "<p>quux</p>\n"
+            "<pre><code>code\n</code></pre>\n"
+            '<p><a href="#fnref-1" class="footnote-backref"'
+            ' aria-label="Back to reference 1">↩</a>'
+            ' <a href="#fnref-1-2" class="footnote-backref"'
+            ' aria-label="Back to reference 2">↩<sup>2</sup></a></p>\n'
+            "</li>\n"
+            '<li id="fn-2">\n'
+            '<p>qux <a href="#fnref-2" class="footnote-backref"'
+            ' aria-label="Back to reference 1">↩</a></p>\n'
+            "</li>\n"
+            "</ol>\n"
+            "</section>\n"
+        )
+
+    def test_collect_footnotes_unreferenced(self) -> None:
+        options = Options(footnotes=True, collect_footnotes=True)
+
+        assert render(["foo\n\n[^1]: bar"], options)[0] == "<p>foo</p>\n"
+
+    def test_void_elements_self_close(self) -> None:
+        markdown = "- [x] foo\n\nbar  \nbaz\n\n---\n\n![qux](quux.png)\n"
+        html = render([markdown], Options(tasklists=True))[0]
+
+        assert '<input disabled="" type="checkbox" checked=""/>' in html
+        assert "<br />" in html
+        assert "<hr />" in html
+        assert '<img src="quux.png" alt="qux" />' in html
+
+    def test_math_delimiters(self) -> None:
+        markdown = r"\(a * b\) and \[c\], but not `\(d\)` or \\(e\)"
+
+        options = Options(
+            math=lambda buffer, display: f"<m{int(display)}>{buffer}</m{int(display)}>",
+            math_delimiters=[("\\(", "\\)", False), ("\\[", "\\]", True)],
+        )
+
+        html = render([markdown], options)[0]
+        code = "<code>\\(d\\)</code>"
+
+        assert html == f"<p><m0>a * b</m0> and <m1>c</m1>, but not {code} or \\(e)</p>\n"
+
+    def test_compact(self) -> None:
+        markdown = "# a\n\nb *c* **d**\ne\n\n```\n  f\n\n  g\n```\n\n- h\n- `i  j`\n"
+        html = render([markdown], Options(compact=True))[0]
+
+        assert html == (
+            "<h1>a</h1><p>b <em>c</em> <strong>d</strong>\ne</p>"
+            "<pre><code>  f\n\n  g\n</code></pre><ul><li>h</li><li><code>i  j</code></li></ul>"
+        )
+
+    def test_compact_pre(self) -> None:
+        pre = "<pre>\n  a  \n  <b> b </b>\n</pre>"
+        html = render([f"<div>\n{pre}\n</div>\n\nc\n"], Options(compact=True))[0]
+
+        assert html == f"<div>{pre}</div><p>c</p>"
+
+    def test_footnote_prefix(self) -> None:
+        markdown = "a[^1]\n\n[^1]: b\n"
+        first, second = (
+            render([markdown], Options(footnotes=True, footnote_prefix=prefix))[0]
+            for prefix in ("doc1-", "doc2-")
+        )
+
+        assert first == (
+            '<p>a<sup class="footnote-reference"><a href="#doc1-1">1</a></sup></p>\n'
+            '<div class="footnote-definition" id="doc1-1">'
+            '<sup class="footnote-definition-label">1</sup>\n<p>b</p>\n</div>\n'
+        )
+
+        assert second == first.replace("doc1-", "doc2-")
+
+    def test_footnote_prefix_collected(self) -> None:
+        options = Options(footnotes=True, collect_footnotes=True, footnote_prefix="doc1-")
+        html = render(["a[^1]\n\n[^1]: b\n"], options)[0]
+
+        assert re.findall(r'id="([^"]*)"', html) == ["doc1-fnref-1", "doc1-footnote-label", "doc1-fn-1"]
+        assert re.findall(r'href="([^"]*)"', html) == ["#doc1-fn-1", "#doc1-fnref-1"]
+
+    def test_footnotes_title(self) -> None:
+        options = Options(footnotes=True, collect_footnotes=True, footnotes_title="Notes & Co")
+        html = render("a[^1]\n\n[^1]: b\n", options)
+
+        assert '<h2 id="footnote-label" class="sr-only">Notes &amp; Co</h2>' in html
+        assert "Footnotes" not in html
+
+    def test_accessible_footnotes(self) -> None:
+        options = Options(footnotes=True, accessible=True)
+
+        assert render("a[^1]\n\n[^1]: b\n", options) == (
+            '<p>a<sup class="footnote-reference">'
+            '<a href="#1" role="doc-noteref" aria-describedby="1">1</a></sup></p>\n'
+            '<div class="footnote-definition" id="1" role="doc-footnote">'
+            '<sup class="footnote-definition-label">1</sup>\n<p>b</p>\n</div>\n'
+        )
+
+    def test_accessible_footnotes_collected(self) -> None:
+        options = Options(footnotes=True, collect_footnotes=True, accessible=True)
+        html = render("a[^1] b[^1]\n\n[^1]: c\n", options)
+
+        assert html.count('role="doc-noteref" aria-describedby="footnote-label"') == 2
+        assert '<section class="footnotes" data-footnotes role="doc-endnotes">' in html
+        assert re.findall(r'aria-label="([^"]*)" role="doc-backlink"', html) == [
+            "Back to reference 1",
+            "Back to reference 2",
+        ]
+
+    def test_accessible_links_and_tasklists(self) -> None:
+        options = Options(tasklists=True, nofollow_external=True, accessible=True)
+
+        assert render("- [x] [a](https://example.com)\n- [ ] b\n", options) == (
+            '<ul>\n<li><input disabled="" type="checkbox" checked="" aria-label="Completed task"/>\n'
+            '<a href="https://example.com" rel="nofollow noopener" target="_blank">'
+            'a<span class="sr-only"> (opens in a new tab)</span></a></li>\n'
+            '<li><input disabled="" type="checkbox" aria-label="Incomplete task"/>\nb</li>\n</ul>\n'
+        )
+
+    def test_allowed_html_tags(self) -> None:
+        markdown = "H<sub>2</sub>O <u>is</u> <B>wet</B>\n\n<div>\n<script>alert(1)</script>\n</div>\n"
+        html = render([markdown], Options(allowed_html_tags=["b", "sub"]))[0]
+
+        assert html == "<p>H<sub>2</sub>O is <b>wet</b></p>\n"
+
+    def test_allowed_html_tags_escape(self) -> None:
+        options = Options(allowed_html_tags=["b"], disallowed_html="escape")
+        html = render(["<i>a</i> <b>b</b> <!-- c -->\n"], options)[0]
+
+        assert html == "<p>&lt;i&gt;a&lt;/i&gt; <b>b</b> &lt;!-- c --&gt;</p>\n"
+
+    def test_allowed_html_tags_attributes(self) -> None:
+        markdown = (
+            '<b title="t" onclick="f()" style="color: red">a</b> '
+            '<a href="jav&#x61;script:f()" class="c">b</a> '
+            '<a href="https://example.com/?a=1&amp;b=2">c</a>\n'
+        )
+        html = render([markdown], Options(allowed_html_tags=["a", "b"]))[0]
+
+        assert html == (
+            '<p><b title="t">a</b> <a class="c">b</a> '
+            '<a href="https://example.com/?a=1&amp;b=2">c</a></p>\n'
+        )
+
+    def test_allowed_html_tags_malformed(self) -> None:
+        markdown = '<div>\n<b\ntitle="a">b</b> 1 < 2\n<i title="c\n</div>\n'
+        options = Options(allowed_html_tags=["b", "i"], disallowed_html="escape")
+        html = render([markdown], options)[0]
+
+        assert html == (
+            '&lt;div&gt;\n<b title="a">b</b> 1 &lt; 2\n&lt;i title=&quot;c\n&lt;/div&gt;\n'
+        )
+
+    def test_figure_images(self) -> None:
+        html = render(['![a](b.png "A <caption>")\n'], Options(figure_images=True))[0]
+
+        assert html == '<figure><img src="b.png" alt="a" /><figcaption>A &lt;caption&gt;</figcaption></figure>\n'
+
+    def test_figure_images_inline(self) -> None:
+        markdown = ['See ![a](b.png "c").\n', '![a](b.png "c") and ![d](e.png "f")\n', "![a](b.png)\n"]
+        assert render(markdown, Options(figure_images=True)) == render(markdown)
+
+    def test_emoji(self) -> None:
+        markdown = "Hi :smile:! (:+1:), :heavy_check_mark:. :unknown: 10:30\n\n`:smile:`\n\n```\n:smile:\n```\n"
+        html = render([markdown], Options(emoji=True))[0]
+
+        assert html == (
+            "<p>Hi 😄! (👍), ✔️. :unknown: 10:30</p>\n<p><code>:smile:</code></p>\n<pre><code>:smile:\n</code></pre>\n"
+        )
+
+    def test_emoji_map(self) -> None:
+        options = Options(emoji=True, emoji_map={"shipit": "🐿️", "smile": ":)"})
+        html = render([":shipit: :smile: :tada:"], options)[0]
+
+        assert html == "<p>🐿️ :) 🎉</p>\n"
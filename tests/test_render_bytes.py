@@ -0,0 +1,30 @@
+"""Test the render_bytes function."""
+
+# ruff: noqa: D101, D102, S101
+
+import pytest
+
+from pulldown_cmark import BadCallbackError, Options, render, render_bytes
+
+
+class TestRenderBytes:
+    def test_single(self) -> None:
+        assert render_bytes("*café*") == "<p><em>café</em></p>\n".encode()
+        assert render_bytes(b"*foo*") == b"<p><em>foo</em></p>\n"
+
+    def test_batch(self) -> None:
+        markdown = ["# foo", "~~bar~~", "ü"]
+        options = Options(strikethrough=True)
+
+        assert render_bytes(markdown, options) == [html.encode() for html in render(markdown, options)]
+
+    def test_error_index(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        with pytest.raises(BadCallbackError, match="while rendering input 1"):
+            _ = render_bytes(["foo", "```\nbar\n```"], Options(code=callback))
+
+    def test_not_iterable(self) -> None:
+        with pytest.raises(TypeError):
+            _ = render_bytes(1)  # pyright: ignore[reportCallIssue, reportArgumentType]
@@ -0,0 +1,91 @@
+"""Test the render_cached function."""
+
+# ruff: noqa: D101, D102, S101
+
+import gc
+import weakref
+
+import pytest
+
+from pulldown_cmark import BadCallbackError, Cache, Options, render, render_cached
+
+
+class TestRenderCached:
+    def test_matches_render(self) -> None:
+        markdown = ["# foo", "*bar*", "# foo"]
+        cache = Cache()
+
+        assert render_cached(markdown, None, cache) == render(markdown)
+        assert len(cache) == 2
+
+    def test_hit(self) -> None:
+        calls: list[str] = []
+
+        def callback(buffer: str, _language: str | None, /) -> str:
+            calls.append(buffer)
+            return buffer
+
+        options = Options(code=callback)
+        cache = Cache()
+
+        first = render_cached(["```\nfoo\n```"], options, cache)
+        second = render_cached(["```\nfoo\n```"], options, cache)
+
+        assert first == second == ["foo\n"]
+        assert calls == ["foo\n"]
+
+    def test_keeps_callbacks(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            return buffer
+
+        cache = Cache()
+        _ = render_cached(["```\nfoo\n```"], Options(code=callback), cache)
+        ref = weakref.ref(callback)
+        del callback
+        _ = gc.collect()
+
+        assert ref() is not None
+
+        cache.clear()
+        _ = gc.collect()
+
+        assert ref() is None
+
+    def test_collects_cycles(self) -> None:
+        cache = Cache()
+
+        def callback(buffer: str, _language: str | None, /) -> str:
+            return buffer
+
+        callback.cache = cache  # pyright: ignore[reportFunctionMemberAccess]
+        _ = render_cached(["```\nfoo\n```", "```\nbar\n```"], Options(code=callback), cache)
+        ref = weakref.ref(callback)
+        del cache, callback
+        _ = gc.collect()
+
+        assert ref() is None
+
+    def test_keyed_by_options(self) -> None:
+        cache = Cache()
+
+        assert render_cached(["~~foo~~"], None, cache) == ["<p>~~foo~~</p>\n"]
+        assert render_cached(["~~foo~~"], Options(strikethrough=True), cache) == ["<p><del>foo</del></p>\n"]
+        assert len(cache) == 2
+
+    def test_clear(self) -> None:
+        cache = Cache()
+        _ = render_cached(["foo"], None, cache)
+        cache.clear()
+
+        assert len(cache) == 0
+
+    def test_error_not_cached(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        cache = Cache()
+
+        with pytest.raises(BadCallbackError, match="while rendering input 1"):
+            _ = render_cached(["foo", "```\nbar\n```"], Options(code=callback), cache)
+
+        assert len(cache) <= 1
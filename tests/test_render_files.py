@@ -0,0 +1,28 @@
+"""Test the render_files function."""
+
+# ruff: noqa: D101, D102, S101
+
+from pathlib import Path
+
+import pytest
+
+from pulldown_cmark import render_files
+
+
+class TestRenderFiles:
+    def test_order(self, tmp_path: Path) -> None:
+        (tmp_path / "foo.md").write_text("# foo")
+        (tmp_path / "bar.md").write_text("*bar*")
+
+        paths = [tmp_path / "foo.md", str(tmp_path / "bar.md")]
+
+        assert render_files(paths) == ["<h1>foo</h1>\n", "<p><em>bar</em></p>\n"]
+
+    def test_missing(self, tmp_path: Path) -> None:
+        (tmp_path / "foo.md").write_text("# foo")
+        missing = tmp_path / "bar.md"
+
+        with pytest.raises(FileNotFoundError, match="while rendering input 1") as info:
+            _ = render_files([tmp_path / "foo.md", missing])
+
+        assert info.value.filename == missing
@@ -0,0 +1,38 @@
+"""Test the render_iter function."""
+
+# ruff: noqa: D101, D102, S101
+
+import pytest
+
+from pulldown_cmark import BadCallbackError, Options, render, render_iter
+
+
+class TestRenderIter:
+    def test_matches_render(self) -> None:
+        markdown = ["# foo", "*bar*", "baz"]
+        assert list(render_iter(markdown)) == render(markdown)
+
+    def test_lazy(self) -> None:
+        consumed: list[int] = []
+
+        def inputs():  # noqa: ANN202
+            for index in range(3):
+                consumed.append(index)
+                yield f"{index}"
+
+        results = render_iter(inputs())
+
+        assert consumed == []
+        assert next(results) == "<p>0</p>\n"
+        assert consumed == [0]
+
+    def test_error_index(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        results = render_iter(["foo", "```\nbar\n```"], Options(code=callback))
+
+        assert next(results) == "<p>foo</p>\n"
+
+        with pytest.raises(BadCallbackError, match="while rendering input 1"):
+            _ = next(results)
@@ -0,0 +1,45 @@
+"""Test the render_mapping function."""
+
+# ruff: noqa: D101, D102, S101
+
+import pytest
+
+from pulldown_cmark import BadCallbackError, Options, render_mapping
+
+
+class TestRenderMapping:
+    def test_keys(self) -> None:
+        documents = {"foo": "*foo*", 2: "bar", ("baz", 3): "# baz"}
+
+        assert render_mapping(documents) == {
+            "foo": "<p><em>foo</em></p>\n",
+            2: "<p>bar</p>\n",
+            ("baz", 3): "<h1>baz</h1>\n",
+        }
+
+    def test_error_key(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        documents = {"foo": "foo", "bar": "```\nbar\n```"}
+
+        with pytest.raises(BadCallbackError, match="while rendering document 'bar'"):
+            _ = render_mapping(documents, Options(code=callback))
+
+    def test_not_string(self) -> None:
+        with pytest.raises(TypeError, match="document 'foo' is not a string"):
+            _ = render_mapping({"foo": 1})  # pyright: ignore[reportArgumentType]
+
+    def test_duplicates(self) -> None:
+        calls: list[str] = []
+
+        def callback(buffer: str, _language: str | None, /) -> str:
+            calls.append(buffer)
+            return buffer
+
+        documents = {"foo": "    qux", "bar": "    quux", "baz": "    qux"}
+        html = render_mapping(documents, Options(code=callback))
+
+        assert html == {"foo": "qux", "bar": "quux", "baz": "qux"}
+        assert list(html) == ["foo", "bar", "baz"]
+        assert sorted(calls) == ["quux", "qux"]
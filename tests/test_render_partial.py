@@ -0,0 +1,25 @@
+"""Test the render_partial function."""
+
+# ruff: noqa: D101, D102, S101
+
+from pulldown_cmark import BadCallbackError, Options, render_partial
+
+
+class TestRenderPartial:
+    @staticmethod
+    def code_callback(buffer: str, _language: str | None, /) -> str:
+        if buffer == "fail\n":
+            raise ValueError(buffer)
+
+        return buffer
+
+    def test_keeps_successes(self) -> None:
+        markdown = ["# foo", "```\nfail\n```", "```\nbar\n```"]
+        results = render_partial(markdown, Options(code=TestRenderPartial.code_callback))
+
+        assert results[0] == "<h1>foo</h1>\n"
+        assert isinstance(results[1], BadCallbackError)
+        assert results[2] == "bar\n"
+
+    def test_without_failures(self) -> None:
+        assert render_partial(["*foo*", "bar"]) == ["<p><em>foo</em></p>\n", "<p>bar</p>\n"]
@@ -0,0 +1,50 @@
+"""Test the render_stream function."""
+
+# ruff: noqa: D101, D102, S101
+
+from io import BytesIO, StringIO
+
+import pytest
+
+from pulldown_cmark import BadCallbackError, Options, render, render_stream
+
+
+class TestRenderStream:
+    def test_matches_render(self) -> None:
+        markdown = "# foo\n\n[bar]\n\n" + "*baz* é\n\n" * 20000 + "[bar]: /qux\n"
+        writes: list[str] = []
+
+        class Writer:
+            def write(self, s: str) -> None:
+                writes.append(s)
+
+        render_stream(BytesIO(markdown.encode()), Writer())
+
+        assert len(writes) > 1
+        assert "".join(writes) == render([markdown])[0]
+
+    def test_compact(self) -> None:
+        options = Options(compact=True, wrapper_tag="article")
+        writer = StringIO()
+        render_stream(StringIO("# foo\n\nbar\n"), writer, options)
+
+        assert writer.getvalue() == render(["# foo\n\nbar\n"], options)[0]
+
+    def test_invalid_utf8(self) -> None:
+        with pytest.raises(ValueError, match="not valid UTF-8"):
+            render_stream(BytesIO(b"\xff"), StringIO())
+
+    def test_write_error(self) -> None:
+        class Writer:
+            def write(self, _s: str) -> None:
+                raise OSError
+
+        with pytest.raises(OSError):  # noqa: PT011
+            render_stream(StringIO("foo"), Writer())
+
+    def test_callback_error(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        with pytest.raises(BadCallbackError):
+            render_stream(StringIO("```\nfoo\n```"), StringIO(), Options(code=callback))
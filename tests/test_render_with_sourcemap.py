@@ -0,0 +1,69 @@
+"""Test the render_with_sourcemap function."""
+
+# ruff: noqa: D101, D102, S101
+
+import pytest
+
+from pulldown_cmark import BadCallbackError, Options, render, render_with_sourcemap
+
+
+class TestRenderWithSourcemap:
+    def test_blocks(self) -> None:
+        markdown = "# foo\n\nbar *baz*\n\n- qux\n  > quux\n"
+        html, sourcemap = render_with_sourcemap(markdown)
+
+        assert html == render([markdown])[0]
+        assert sourcemap == [(0, 6, 0), (7, 17, 13), (18, 33, 37)]
+
+    def test_offsets(self) -> None:
+        markdown = "é\n\n```\nfoo\n```\n\n<div>bar</div>\n"
+        html, sourcemap = render_with_sourcemap(markdown)
+        source, output = markdown.encode(), html.encode()
+
+        assert [source[start:end] for start, end, _ in sourcemap] == [
+            b"\xc3\xa9\n",
+            b"```\nfoo\n```",
+            b"<div>bar</div>\n",
+        ]
+        assert [output[offset:][:5] for _, _, offset in sourcemap] == [
+            b"<p>\xc3\xa9",
+            b"<pre>",
+            b"<div>",
+        ]
+
+    def test_callback_output(self) -> None:
+        options = Options(code=lambda buffer, _: f"<pre>{buffer}</pre>")
+        html, sourcemap = render_with_sourcemap("foo\n\n    bar\n\nbaz\n", options)
+
+        assert html == "<p>foo</p>\n<pre>bar\n</pre>\n<p>baz</p>\n"
+        assert sourcemap == [(0, 4, 0), (9, 13, 11), (14, 18, 26)]
+
+    def test_bad_callback(self) -> None:
+        def code(_buffer: str, _language: str | None) -> str:
+            raise ValueError
+
+        with pytest.raises(BadCallbackError):
+            _ = render_with_sourcemap("    foo", Options(code=code))
+
+    def test_wrapper(self) -> None:
+        html, sourcemap = render_with_sourcemap("foo", Options(wrapper_tag="div"))
+
+        assert html == "<div>\n<p>foo</p>\n</div>\n"
+        assert sourcemap == [(0, 3, 6)]
+
+    def test_accessible_footnotes(self) -> None:
+        markdown = "a[^1]\n\n[^1]: note\n\nb\n"
+        options = Options(footnotes=True, accessible=True)
+        html, sourcemap = render_with_sourcemap(markdown, options)
+
+        assert html == render([markdown], options)[0]
+        assert [markdown[start:end] for start, end, _ in sourcemap] == ["a[^1]\n", "[^1]: note\n\n", "b\n"]
+        assert [html[offset:][:4] for _, _, offset in sourcemap] == ["<p>a", "<div", "<p>b"]
+
+    def test_code_block_class(self) -> None:
+        markdown = "```rust\nx\n```\n\nfoo\n"
+        options = Options(code_block_class="hl")
+        html, sourcemap = render_with_sourcemap(markdown, options)
+
+        assert html == render([markdown], options)[0]
+        assert sourcemap == [(0, 13, 0), (15, 19, 52)]
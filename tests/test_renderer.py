@@ -0,0 +1,43 @@
+"""Test the Renderer class."""
+
+# ruff: noqa: D101, D102, S101
+
+import pytest
+
+from pulldown_cmark import BadCallbackError, Options, Renderer, render
+
+
+class TestRenderer:
+    def test_matches_render(self) -> None:
+        renderer = Renderer(Options(strikethrough=True))
+        markdown = ["# foo\n\nbar baz qux", "~~a~~", b"*b*", ""]
+
+        assert [renderer.render(text) for text in markdown] == render(markdown, Options(strikethrough=True))
+
+    def test_default_options(self) -> None:
+        assert Renderer().render("~~foo~~") == "<p>~~foo~~</p>\n"
+
+    def test_compact(self) -> None:
+        renderer = Renderer(Options(compact=True))
+
+        assert renderer.render("# foo\n\nbar") == "<h1>foo</h1><p>bar</p>"
+        assert renderer.render("baz") == "<p>baz</p>"
+
+    def test_context(self) -> None:
+        def code(buffer: str, _language: str | None, /, context: str | None) -> str:
+            return f"{context}: {buffer}"
+
+        renderer = Renderer(Options(code=code))
+
+        assert renderer.render("```\nfoo\n```", context="a") == "a: foo\n"
+
+    def test_error_keeps_renderer(self) -> None:
+        def code(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        renderer = Renderer(Options(code=code))
+
+        with pytest.raises(BadCallbackError):
+            _ = renderer.render("```\nfoo\n```")
+
+        assert renderer.render("foo") == "<p>foo</p>\n"
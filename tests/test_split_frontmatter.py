@@ -0,0 +1,31 @@
+"""Test the split_frontmatter function."""
+
+# ruff: noqa: D101, D102, S101
+
+from pulldown_cmark import split_frontmatter
+
+
+class TestSplitFrontmatter:
+    def test_yaml(self) -> None:
+        markdown = "---\ntitle: foo\n---\n# bar\n"
+        assert split_frontmatter(markdown) == ("title: foo\n", "# bar\n")
+
+    def test_yaml_dots(self) -> None:
+        markdown = "---\ntitle: foo\n...\n# bar\n"
+        assert split_frontmatter(markdown) == ("title: foo\n", "# bar\n")
+
+    def test_toml(self) -> None:
+        markdown = '+++\ntitle = "foo"\n+++\n# bar\n'
+        assert split_frontmatter(markdown) == ('title = "foo"\n', "# bar\n")
+
+    def test_mismatched_delimiters(self) -> None:
+        markdown = '+++\ntitle = "foo"\n---\n# bar\n'
+        assert split_frontmatter(markdown) == (None, markdown)
+
+    def test_unclosed(self) -> None:
+        markdown = "---\ntitle: foo\n# bar\n"
+        assert split_frontmatter(markdown) == (None, markdown)
+
+    def test_absent(self) -> None:
+        markdown = "# foo\n---\n"
+        assert split_frontmatter(markdown) == (None, markdown)
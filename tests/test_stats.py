@@ -0,0 +1,29 @@
+"""Test the stats function."""
+
+# ruff: noqa: D101, D102, S101
+
+from textwrap import dedent
+
+from pulldown_cmark import stats
+
+
+class TestStats:
+    def test_counts(self) -> None:
+        assert stats(["# foo\n\n*bar* `baz`"]) == [{"words": 3, "characters": 11, "reading_minutes": 1}]
+
+    def test_excludes_code_blocks(self) -> None:
+        markdown = dedent("""
+        foo
+
+        ```
+        bar baz qux
+        ```
+
+        <div>quux</div>
+        """)
+
+        assert stats([markdown])[0]["words"] == 1
+
+    def test_reading_minutes(self) -> None:
+        assert stats(["foo " * 401])[0]["reading_minutes"] == 3
+        assert stats([""])[0]["reading_minutes"] == 0
@@ -0,0 +1,45 @@
+"""Test the to_json function."""
+
+# ruff: noqa: D101, D102, S101
+
+import json
+
+from pulldown_cmark import to_json
+
+
+class TestToJson:
+    def test_nesting(self) -> None:
+        tree = json.loads(to_json(["# foo *bar*"])[0])
+
+        assert tree == {
+            "type": "document",
+            "children": [
+                {
+                    "type": "heading",
+                    "level": 1,
+                    "id": None,
+                    "classes": [],
+                    "attrs": [],
+                    "children": [
+                        {"type": "text", "value": "foo "},
+                        {"type": "emphasis", "children": [{"type": "text", "value": "bar"}]},
+                    ],
+                },
+            ],
+        }
+
+    def test_fields(self) -> None:
+        tree = json.loads(to_json(['[foo](/bar "baz")\n\n```rust\nqux\n```'])[0])
+        link = tree["children"][0]["children"][0]
+        code = tree["children"][1]
+
+        assert (link["type"], link["dest_url"], link["title"]) == ("link", "/bar", "baz")
+        assert code == {
+            "type": "code_block",
+            "fenced": True,
+            "info": "rust",
+            "children": [{"type": "text", "value": "qux\n"}],
+        }
+
+    def test_batch(self) -> None:
+        assert len(to_json(["foo", "bar", "baz"])) == 3
@@ -0,0 +1,37 @@
+"""Test the to_plaintext function."""
+
+# ruff: noqa: D101, D102, S101
+
+from textwrap import dedent
+
+from pulldown_cmark import Options, to_plaintext
+
+
+class TestToPlaintext:
+    def test_drops_markup(self) -> None:
+        markdown = "# foo *bar*\n\nbaz `qux` <b>quux</b>"
+        assert to_plaintext([markdown]) == ["foo bar\nbaz qux quux"]
+
+    def test_breaks(self) -> None:
+        assert to_plaintext(["foo\nbar  \nbaz"]) == ["foo bar\nbaz"]
+
+    def test_code_blocks(self) -> None:
+        markdown = dedent("""
+        foo
+
+        ```
+        bar
+        ```
+        """)
+
+        assert to_plaintext([markdown]) == ["foo\nbar"]
+
+    def test_skips_front_matter(self) -> None:
+        markdown = "---\ntitle: foo\n---\nbar"
+        assert to_plaintext([markdown], Options(yaml_style_metadata_blocks=True)) == ["bar"]
+
+    def test_skips_callbacks(self) -> None:
+        def callback(buffer: str, _language: str | None, /) -> str:
+            raise ValueError(buffer)
+
+        assert to_plaintext(["```\nfoo\n```"], Options(code=callback)) == ["foo"]
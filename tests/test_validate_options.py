@@ -0,0 +1,51 @@
+"""Test the validate_options function."""
+
+# ruff: noqa: D101, D102, S101
+
+import pytest
+
+from pulldown_cmark import BadCallbackError, Options, validate_options
+
+
+class TestValidateOptions:
+    def test_valid(self) -> None:
+        options = Options(
+            math=lambda *_: None,
+            code={"python": lambda *_: "", "*": lambda *_: [("", False)]},
+            broken_link=lambda _: ("foo", "bar"),
+            wikilink=lambda _: "foo",
+            tasklist_marker=lambda _: "",
+        )
+
+        validate_options(options)
+        validate_options(Options())
+
+    def test_raises(self) -> None:
+        def code(buffer: str, language: str | None) -> str:
+            raise ValueError(buffer, language)
+
+        with pytest.raises(BadCallbackError, match="`code` callback") as info:
+            validate_options(Options(code=code))
+
+        assert isinstance(info.value.__cause__, ValueError)
+        assert info.value.__cause__.args == ("", None)
+
+    def test_bad_return(self) -> None:
+        options = Options(code={"python": lambda *_: 1})  # pyright: ignore[reportArgumentType]
+
+        with pytest.raises(BadCallbackError, match="`code` callback for `python`") as info:
+            validate_options(options)
+
+        assert isinstance(info.value.__cause__, TypeError)
+
+    def test_batch_callbacks(self) -> None:
+        def code(items: list[tuple[str, str | None]], /) -> list[str]:
+            assert items == [("", None)]
+            return [""]
+
+        validate_options(Options(code=code, math=lambda items: [None], batch_callbacks=True))
+
+        with pytest.raises(BadCallbackError, match="batched `code` callback") as info:
+            validate_options(Options(code=lambda _: ["", ""], batch_callbacks=True))
+
+        assert isinstance(info.value.__cause__, ValueError)
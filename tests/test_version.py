@@ -0,0 +1,16 @@
+"""Test the version attributes."""
+
+# ruff: noqa: D101, D102, S101
+
+import re
+from importlib.metadata import version
+
+import pulldown_cmark
+
+
+class TestVersion:
+    def test_version(self) -> None:
+        assert pulldown_cmark.__version__ == version("pulldown-cmark")
+
+    def test_pulldown_cmark_version(self) -> None:
+        assert re.fullmatch(r"\d+\.\d+\.\d+", pulldown_cmark.PULLDOWN_CMARK_VERSION)